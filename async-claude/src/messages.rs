@@ -84,7 +84,11 @@ impl MessageContent {
 #[serde(tag = "type")]
 pub enum BaseContentBlock {
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
     #[serde(rename = "thinking")]
     Thinking {
         thinking: String,
@@ -107,21 +111,83 @@ pub struct ToolUseContentBlock {
 #[serde(tag = "type")]
 pub enum RequestOnlyContentBlock {
     #[serde(rename = "image")]
-    Image { source: ImageSource },
+    Image {
+        source: ImageSource,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
     #[serde(rename = "document")]
     Document {
         #[serde(skip_serializing_if = "Option::is_none")]
         source: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
     },
     #[serde(rename = "tool_result")]
     ToolResult {
         tool_use_id: String,
-        content: String,
+        #[serde(default)]
+        content: ToolResultContent,
+        /// Set when the tool call failed, `content` then describes the error.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        is_error: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
     },
 }
 
+/// The result of a tool call, either plain text or text, image and document blocks.
+#[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ToolResultContent {
+    Text(String),
+    Blocks(Vec<ContentBlock>),
+}
+
+impl Default for ToolResultContent {
+    fn default() -> Self {
+        ToolResultContent::Text(String::new())
+    }
+}
+
+impl From<String> for ToolResultContent {
+    fn from(text: String) -> Self {
+        ToolResultContent::Text(text)
+    }
+}
+
+impl From<&str> for ToolResultContent {
+    fn from(text: &str) -> Self {
+        ToolResultContent::Text(text.to_string())
+    }
+}
+
+impl ToolResultContent {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            ToolResultContent::Text(text) => text.trim().is_empty(),
+            ToolResultContent::Blocks(blocks) => blocks.iter().all(ContentBlock::is_empty),
+        }
+    }
+
+    /// The text blocks joined by newlines, images and documents are skipped.
+    pub fn text(&self) -> String {
+        match self {
+            ToolResultContent::Text(text) => text.clone(),
+            ToolResultContent::Blocks(blocks) => blocks
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::Base(BaseContentBlock::Text { text, .. }) => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
 // Content blocks that can be used in request body (all types)
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
@@ -136,10 +202,25 @@ pub enum ContentBlock {
 pub enum ResponseContentBlock {
     Base(BaseContentBlock),
     RedactedThinking(RedactedThinkingContentBlock),
+    ServerToolResult(ServerToolResultContentBlock),
 }
 
+/// Results of the tools Claude calls itself, such as the tools of an MCP server.
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
-#[serde(untagged)]
+#[serde(tag = "type")]
+pub enum ServerToolResultContentBlock {
+    #[serde(rename = "mcp_tool_result")]
+    McpToolResult {
+        tool_use_id: String,
+        #[serde(default)]
+        content: ToolResultContent,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        is_error: Option<bool>,
+    },
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
 pub enum RedactedThinkingContentBlock {
     #[serde(rename = "redacted_thinking")]
     RedactedThinking { data: String },
@@ -149,7 +230,7 @@ impl ContentBlock {
     pub fn is_empty(&self) -> bool {
         match self {
             ContentBlock::Base(base) => match base {
-                BaseContentBlock::Text { text } => text.trim().is_empty(),
+                BaseContentBlock::Text { text, .. } => text.trim().is_empty(),
                 BaseContentBlock::ToolUse(tool_use) => {
                     tool_use.id.is_empty()
                         || tool_use.name.is_empty()
@@ -158,28 +239,53 @@ impl ContentBlock {
                 BaseContentBlock::Thinking { thinking, .. } => thinking.trim().is_empty(),
             },
             ContentBlock::RequestOnly(req_only) => match req_only {
-                RequestOnlyContentBlock::Image { source } => match source {
+                RequestOnlyContentBlock::Image { source, .. } => match source {
                     ImageSource::Base64 { media_type, data } => {
                         media_type.trim().is_empty() || data.trim().is_empty()
                     }
+                    ImageSource::Url { url } => url.trim().is_empty(),
                 },
-                RequestOnlyContentBlock::Document { source, id } => {
+                RequestOnlyContentBlock::Document { source, id, .. } => {
                     source.is_none() || id.is_none()
                 }
-                RequestOnlyContentBlock::ToolResult {
-                    tool_use_id,
-                    content,
-                } => tool_use_id.is_empty() || content.trim().is_empty(),
+                // a result without content still answers its tool_use
+                RequestOnlyContentBlock::ToolResult { tool_use_id, .. } => tool_use_id.is_empty(),
             },
             ContentBlock::RedactedThinking(redacted_thinking) => match redacted_thinking {
                 RedactedThinkingContentBlock::RedactedThinking { data } => data.is_empty(),
             },
         }
     }
+
+    /// The cache breakpoint of the block, `None` if it isn't set.
+    pub fn cache_control(&self) -> Option<&CacheControl> {
+        match self {
+            ContentBlock::Base(BaseContentBlock::Text { cache_control, .. })
+            | ContentBlock::RequestOnly(
+                RequestOnlyContentBlock::Image { cache_control, .. }
+                | RequestOnlyContentBlock::Document { cache_control, .. }
+                | RequestOnlyContentBlock::ToolResult { cache_control, .. },
+            ) => cache_control.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The cache breakpoint slot of the block, `None` if the block can't be a breakpoint, e.g. thinking.
+    pub fn cache_control_mut(&mut self) -> Option<&mut Option<CacheControl>> {
+        match self {
+            ContentBlock::Base(BaseContentBlock::Text { cache_control, .. })
+            | ContentBlock::RequestOnly(
+                RequestOnlyContentBlock::Image { cache_control, .. }
+                | RequestOnlyContentBlock::Document { cache_control, .. }
+                | RequestOnlyContentBlock::ToolResult { cache_control, .. },
+            ) => Some(cache_control),
+            _ => None,
+        }
+    }
 }
 
 // Delta content blocks for streaming
-// TODO: it should include a redacted_thinking delta, but the docuement dind't include it as example
+// redacted_thinking has no delta, the whole block comes with content_block_start
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
 #[serde(tag = "type")]
 pub enum DeltaContentBlock {
@@ -191,6 +297,9 @@ pub enum DeltaContentBlock {
     ThinkingDelta { thinking: String },
     #[serde(rename = "signature_delta")]
     SignatureDelta { signature: String },
+    /// A delta type this crate doesn't know yet.
+    #[serde(other)]
+    Unknown,
 }
 
 impl DeltaContentBlock {
@@ -200,6 +309,7 @@ impl DeltaContentBlock {
             DeltaContentBlock::InputJsonDelta { partial_json } => partial_json.is_empty(),
             DeltaContentBlock::ThinkingDelta { thinking } => thinking.is_empty(),
             DeltaContentBlock::SignatureDelta { signature } => signature.is_empty(),
+            DeltaContentBlock::Unknown => true,
         }
     }
 }
@@ -209,6 +319,8 @@ impl DeltaContentBlock {
 pub enum ImageSource {
     #[serde(rename = "base64")]
     Base64 { media_type: String, data: String },
+    #[serde(rename = "url")]
+    Url { url: String },
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
@@ -222,6 +334,12 @@ pub enum DocumentSource {
 pub struct Usage {
     pub input_tokens: Option<u32>,
     pub output_tokens: u32,
+    /// The number of input tokens used to create the cache entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_input_tokens: Option<u32>,
+    /// The number of input tokens read from the cache.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_input_tokens: Option<u32>,
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
@@ -231,4 +349,11 @@ pub enum StopReason {
     MaxTokens,
     StopSequence,
     ToolUse,
+    /// A long running turn was paused, send the response back to let the model continue.
+    PauseTurn,
+    /// The model declined to respond.
+    Refusal,
+    /// A stop reason this crate doesn't know yet.
+    #[serde(other)]
+    Unknown,
 }
@@ -26,6 +26,8 @@ pub struct Request {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<Tool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub thinking: Option<Thinking>,
 }
 
@@ -34,6 +36,32 @@ pub struct Tool {
     pub name: Cow<'static, str>,
     pub description: Option<Cow<'static, str>>,
     pub input_schema: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControl>,
+}
+
+/// How the model should use the provided tools.
+#[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ToolChoice {
+    /// The model decides whether to call a tool.
+    Auto {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        disable_parallel_tool_use: Option<bool>,
+    },
+    /// The model must call one of the tools.
+    Any {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        disable_parallel_tool_use: Option<bool>,
+    },
+    /// The model must call the named tool.
+    Tool {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        disable_parallel_tool_use: Option<bool>,
+    },
+    /// The model must not call any tool.
+    None,
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
@@ -64,9 +92,24 @@ pub enum SystemMessageType {
     Text,
 }
 
+/// Marks the end of a cacheable prefix, a request can have at most 4 breakpoints.
 #[derive(Debug, Deserialize, Clone, Default, PartialEq, Serialize)]
 pub struct CacheControl {
     pub r#type: CacheControlType,
+    /// Defaults to 5 minutes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<CacheTtl>,
+}
+
+impl CacheControl {
+    pub fn ephemeral() -> Self {
+        Self::default()
+    }
+
+    pub fn ttl(mut self, ttl: CacheTtl) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, Default, PartialEq, Serialize)]
@@ -76,6 +119,111 @@ pub enum CacheControlType {
     Ephemeral,
 }
 
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CacheTtl {
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+}
+
+/// The maximum number of cache breakpoints in a request.
+pub const MAX_CACHE_BREAKPOINTS: usize = 4;
+
+impl Request {
+    /// Place cache breakpoints on the last tool, the last system block and the last cacheable block of the
+    /// last two user messages, in that order, without exceeding [`MAX_CACHE_BREAKPOINTS`] in total.
+    /// A string system prompt or message content is turned into a single text block.
+    ///
+    /// Returns the number of breakpoints placed.
+    pub fn add_cache_breakpoints(&mut self, cache_control: CacheControl) -> usize {
+        let mut available = MAX_CACHE_BREAKPOINTS.saturating_sub(self.cache_breakpoints());
+        let mut placed = 0;
+        let mut place = |slot: &mut Option<CacheControl>| {
+            if available > 0 && slot.is_none() {
+                *slot = Some(cache_control.clone());
+                available -= 1;
+                placed += 1;
+            }
+        };
+
+        if let Some(tool) = self.tools.as_mut().and_then(|tools| tools.last_mut()) {
+            place(&mut tool.cache_control);
+        }
+
+        if let Some(System::Text(text)) = self.system.as_mut() {
+            self.system = Some(System::Blocks(vec![
+                SystemMessage {
+                    r#type: SystemMessageType::Text,
+                    text: std::mem::take(text),
+                    cache_control: None,
+                },
+            ]));
+        }
+        if let Some(System::Blocks(blocks)) = self.system.as_mut() {
+            if let Some(block) = blocks.last_mut() {
+                place(&mut block.cache_control);
+            }
+        }
+
+        for message in self
+            .messages
+            .iter_mut()
+            .rev()
+            .filter(|message| message.role == Role::User)
+            .take(2)
+        {
+            if let MessageContent::Text(text) = &mut message.content {
+                message.content = MessageContent::Blocks(vec![
+                    ContentBlock::Base(BaseContentBlock::Text {
+                        text: std::mem::take(text),
+                        cache_control: None,
+                    }),
+                ]);
+            }
+            if let MessageContent::Blocks(blocks) = &mut message.content {
+                if let Some(slot) = blocks
+                    .iter_mut()
+                    .rev()
+                    .find_map(|block| block.cache_control_mut())
+                {
+                    place(slot);
+                }
+            }
+        }
+        placed
+    }
+
+    /// The number of cache breakpoints set in the request.
+    pub fn cache_breakpoints(&self) -> usize {
+        let tools = self
+            .tools
+            .iter()
+            .flatten()
+            .filter(|tool| tool.cache_control.is_some())
+            .count();
+        let system = match self.system.as_ref() {
+            Some(System::Blocks(blocks)) => blocks
+                .iter()
+                .filter(|block| block.cache_control.is_some())
+                .count(),
+            _ => 0,
+        };
+        let messages = self
+            .messages
+            .iter()
+            .map(|message| match &message.content {
+                MessageContent::Blocks(blocks) => blocks
+                    .iter()
+                    .filter(|block| block.cache_control().is_some())
+                    .count(),
+                MessageContent::Text(_) => 0,
+            })
+            .sum::<usize>();
+        tools + system + messages
+    }
+}
+
 /// process_messages take arbitrary user input messages and process them to ensure them conform to Anthropic API requirements.
 /// the requirements are:
 /// 1. start with user message
@@ -117,11 +265,16 @@ pub fn process_messages(messages: &[Message]) -> Vec<Message> {
                         prev.retain(|v| !v.is_empty());
                         prev.push(ContentBlock::Base(BaseContentBlock::Text {
                             text: curr.clone(),
+                            cache_control: None,
                         }));
                     }
                     (MessageContent::Text(prev), MessageContent::Blocks(curr)) => {
-                        let mut blocks =
-                            vec![ContentBlock::Base(BaseContentBlock::Text { text: prev.clone() })];
+                        let mut blocks = vec![
+                            ContentBlock::Base(BaseContentBlock::Text {
+                                text: prev.clone(),
+                                cache_control: None,
+                            }),
+                        ];
                         let curr_clone: Vec<_> =
                             curr.clone().into_iter().filter(|v| !v.is_empty()).collect();
                         blocks.extend(curr_clone);
@@ -162,7 +315,7 @@ pub fn process_messages(messages: &[Message]) -> Vec<Message> {
                 }
                 MessageContent::Blocks(blocks) => {
                     for block in blocks {
-                        if let ContentBlock::Base(BaseContentBlock::Text { text }) = block {
+                        if let ContentBlock::Base(BaseContentBlock::Text { text, .. }) = block {
                             *text = text.trim_end().to_string();
                         }
                     }
@@ -178,7 +331,7 @@ pub fn process_messages(messages: &[Message]) -> Vec<Message> {
 mod tests {
     use crate::messages::{
         ContentBlock, ImageSource, MessageContent, RequestOnlyContentBlock, Role,
-        ToolUseContentBlock,
+        ToolResultContent, ToolUseContentBlock,
     };
 
     use super::*;
@@ -294,6 +447,7 @@ mod tests {
                             text: "You are a really helpful assistant.".to_string(),
                             cache_control: Some(CacheControl {
                                 r#type: CacheControlType::Ephemeral,
+                                ttl: None,
                             }),
                         },
                     ])),
@@ -390,9 +544,58 @@ mod tests {
                                     media_type: "image/jpeg".to_string(),
                                     data: "/9j/4AAQSkZJRg...".to_string(),
                                 },
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "What is in this image?".to_string(),
+                                cache_control: None,
+                            }),
+                        ]),
+                    }],
+                    ..Default::default()
+                },
+            ),
+            (
+                "cache control",
+                r#"{
+                "model": "claude-3-opus-20240229",
+                "max_tokens": 1024,
+                "tools": [{
+                    "name": "get_time",
+                    "description": null,
+                    "input_schema": {"type": "object", "properties": {}},
+                    "cache_control": {"type": "ephemeral", "ttl": "1h"}
+                }],
+                "messages": [
+                    {"role": "user", "content": [
+                        {"type": "text", "text": "What time is it?", "cache_control": {"type": "ephemeral"}},
+                        {"type": "tool_result", "tool_use_id": "toolu_01", "content": "12:00", "cache_control": {"type": "ephemeral", "ttl": "5m"}}
+                    ]}
+                ]
+            }"#,
+                Request {
+                    model: "claude-3-opus-20240229".to_string(),
+                    max_tokens: 1024,
+                    tools: Some(vec![Tool {
+                        name: "get_time".into(),
+                        description: None,
+                        input_schema: serde_json::json!({"type": "object", "properties": {}}),
+                        cache_control: Some(CacheControl::ephemeral().ttl(CacheTtl::OneHour)),
+                    }]),
+                    messages: vec![Message {
+                        role: Role::User,
+                        content: MessageContent::Blocks(vec![
+                            ContentBlock::Base(BaseContentBlock::Text {
+                                text: "What time is it?".to_string(),
+                                cache_control: Some(CacheControl::ephemeral()),
+                            }),
+                            ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult {
+                                tool_use_id: "toolu_01".to_string(),
+                                content: "12:00".into(),
+                                is_error: None,
+                                cache_control: Some(
+                                    CacheControl::ephemeral().ttl(CacheTtl::FiveMinutes),
+                                ),
                             }),
                         ]),
                     }],
@@ -411,6 +614,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn add_cache_breakpoints() {
+        let tool = |name: &str| Tool {
+            name: name.to_string().into(),
+            input_schema: serde_json::json!({"type": "object", "properties": {}}),
+            ..Default::default()
+        };
+        let text = |role: Role, text: &str| Message {
+            role,
+            content: MessageContent::Text(text.to_string()),
+        };
+        let request = Request {
+            system: Some(System::Text("You are a helpful assistant.".to_string())),
+            tools: Some(vec![
+                tool("get_time"),
+                tool("get_weather"),
+            ]),
+            messages: vec![
+                text(Role::User, "first"),
+                text(Role::Assistant, "reply"),
+                text(Role::User, "second"),
+                text(Role::Assistant, "reply"),
+                text(Role::User, "third"),
+            ],
+            ..Default::default()
+        };
+        let mut with_breakpoint = request.clone();
+        with_breakpoint.tools.as_mut().unwrap()[0].cache_control = Some(CacheControl::ephemeral());
+
+        let tests = vec![
+            (
+                "empty",
+                request,
+                4,
+                vec![
+                    false, true, true, false, true, true,
+                ],
+            ),
+            (
+                "existing breakpoint",
+                with_breakpoint,
+                3,
+                vec![
+                    true, true, true, false, true, false,
+                ],
+            ),
+        ];
+        for (name, mut request, placed, want) in tests {
+            assert_eq!(
+                request.add_cache_breakpoints(CacheControl::ephemeral()),
+                placed,
+                "{}",
+                name
+            );
+            assert_eq!(request.cache_breakpoints(), 4, "{}", name);
+            let tools = request.tools.as_ref().unwrap();
+            let Some(System::Blocks(system)) = request.system.as_ref() else {
+                panic!("{}: system should be blocks", name);
+            };
+            let user_messages = [4, 2].map(|i| match &request.messages[i].content {
+                MessageContent::Blocks(blocks) => blocks[0].cache_control().is_some(),
+                MessageContent::Text(_) => false,
+            });
+            let got = vec![
+                tools[0].cache_control.is_some(),
+                tools[1].cache_control.is_some(),
+                system[0].cache_control.is_some(),
+                matches!(request.messages[0].content, MessageContent::Blocks(_)),
+                user_messages[0],
+                user_messages[1],
+            ];
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+
     #[test]
     fn process() {
         let tests = vec![
@@ -438,6 +716,7 @@ mod tests {
                     content: MessageContent::Blocks(vec![
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "hi".to_string(),
+                            cache_control: None,
                         }),
                     ]),
                 }],
@@ -451,6 +730,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "hi".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -463,12 +743,14 @@ mod tests {
                     content: MessageContent::Blocks(vec![
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "hi".to_string(),
+                            cache_control: None,
                         }),
                         ContentBlock::RequestOnly(RequestOnlyContentBlock::Image {
                             source: ImageSource::Base64 {
                                 media_type: "img/png".to_string(),
                                 data: "abcs".to_string(),
                             },
+                            cache_control: None,
                         }),
                     ]),
                 }],
@@ -482,12 +764,14 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "hi".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::RequestOnly(RequestOnlyContentBlock::Image {
                                 source: ImageSource::Base64 {
                                     media_type: "img/png".to_string(),
                                     data: "abcs".to_string(),
                                 },
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -532,12 +816,14 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "hi".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::RequestOnly(RequestOnlyContentBlock::Image {
                                 source: ImageSource::Base64 {
                                     media_type: "img/png".to_string(),
                                     data: "abcs".to_string(),
                                 },
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -556,12 +842,14 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "hi".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::RequestOnly(RequestOnlyContentBlock::Image {
                                 source: ImageSource::Base64 {
                                     media_type: "img/png".to_string(),
                                     data: "abcs".to_string(),
                                 },
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -596,6 +884,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -605,9 +894,11 @@ mod tests {
                     content: MessageContent::Blocks(vec![
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "Hi,".to_string(),
+                            cache_control: None,
                         }),
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "how are you".to_string(),
+                            cache_control: None,
                         }),
                     ]),
                 }],
@@ -620,6 +911,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -633,9 +925,11 @@ mod tests {
                     content: MessageContent::Blocks(vec![
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "how are you".to_string(),
+                            cache_control: None,
                         }),
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "Hi,".to_string(),
+                            cache_control: None,
                         }),
                     ]),
                 }],
@@ -672,6 +966,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -690,9 +985,11 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "Hi,".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -706,6 +1003,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -718,6 +1016,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -727,12 +1026,15 @@ mod tests {
                     content: MessageContent::Blocks(vec![
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "how are you".to_string(),
+                            cache_control: None,
                         }),
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "Hi,".to_string(),
+                            cache_control: None,
                         }),
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "who are you".to_string(),
+                            cache_control: None,
                         }),
                     ]),
                 }],
@@ -745,6 +1047,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -757,6 +1060,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -770,15 +1074,19 @@ mod tests {
                     content: MessageContent::Blocks(vec![
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "how are you".to_string(),
+                            cache_control: None,
                         }),
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "Hi,".to_string(),
+                            cache_control: None,
                         }),
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "who are you".to_string(),
+                            cache_control: None,
                         }),
                         ContentBlock::Base(BaseContentBlock::Text {
                             text: "ho".to_string(),
+                            cache_control: None,
                         }),
                     ]),
                 }],
@@ -791,6 +1099,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -803,6 +1112,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -817,12 +1127,15 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "Hi,".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -840,6 +1153,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -852,6 +1166,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -866,9 +1181,11 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "Hi,".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -877,6 +1194,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -894,6 +1212,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -906,6 +1225,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -920,6 +1240,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -932,9 +1253,11 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "ho".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -948,6 +1271,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -960,6 +1284,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -978,6 +1303,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -986,12 +1312,15 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "Hi,".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "ho".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -1044,6 +1373,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "   ".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -1052,9 +1382,11 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "     ".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "hi".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -1071,6 +1403,7 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you    ".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -1097,9 +1430,11 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "hi".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "how are you".to_string(),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -1108,9 +1443,68 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "hi".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "who are you".to_string(),
+                                cache_control: None,
+                            }),
+                        ]),
+                    },
+                ],
+            ),
+            (
+                "tool_result without content",
+                vec![
+                    Message {
+                        role: Role::User,
+                        content: MessageContent::Text("weather in Paris?".to_string()),
+                    },
+                    Message {
+                        role: Role::Assistant,
+                        content: MessageContent::Blocks(vec![
+                            ContentBlock::Base(BaseContentBlock::ToolUse(ToolUseContentBlock {
+                                id: "toolu_01".to_string(),
+                                name: "get_weather".to_string(),
+                                input: serde_json::json!({"location": "Paris"}),
+                            })),
+                        ]),
+                    },
+                    Message {
+                        role: Role::User,
+                        content: MessageContent::Blocks(vec![
+                            ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult {
+                                tool_use_id: "toolu_01".to_string(),
+                                content: ToolResultContent::default(),
+                                is_error: Some(true),
+                                cache_control: None,
+                            }),
+                        ]),
+                    },
+                ],
+                vec![
+                    Message {
+                        role: Role::User,
+                        content: MessageContent::Text("weather in Paris?".to_string()),
+                    },
+                    Message {
+                        role: Role::Assistant,
+                        content: MessageContent::Blocks(vec![
+                            ContentBlock::Base(BaseContentBlock::ToolUse(ToolUseContentBlock {
+                                id: "toolu_01".to_string(),
+                                name: "get_weather".to_string(),
+                                input: serde_json::json!({"location": "Paris"}),
+                            })),
+                        ]),
+                    },
+                    Message {
+                        role: Role::User,
+                        content: MessageContent::Blocks(vec![
+                            ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult {
+                                tool_use_id: "toolu_01".to_string(),
+                                content: ToolResultContent::default(),
+                                is_error: Some(true),
+                                cache_control: None,
                             }),
                         ]),
                     },
@@ -1181,6 +1575,7 @@ mod tests {
                             },
                             "required": ["location"]
                         }),
+                        cache_control: None,
                     }]),
                     ..Default::default()
                 },
@@ -1269,6 +1664,7 @@ mod tests {
                             },
                             "required": ["location"]
                         }),
+                        cache_control: None,
                     }]),
                     messages: vec![
                         Message {
@@ -1282,6 +1678,7 @@ mod tests {
                             content: MessageContent::Blocks(vec![
                                 ContentBlock::Base(BaseContentBlock::Text {
                                     text: "<thinking>I need to use get_weather, and the user wants SF, which is likely San Francisco, CA.</thinking>".to_string(),
+                                    cache_control: None,
                                 }),
                                 ContentBlock::Base(BaseContentBlock::ToolUse(ToolUseContentBlock {
                                     id: "toolu_01A09q90qw90lq917835lq9".to_string(),
@@ -1298,7 +1695,9 @@ mod tests {
                             content: MessageContent::Blocks(vec![
                                 ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult {
                                     tool_use_id: "toolu_01A09q90qw90lq917835lq9".to_string(),
-                                    content: "15 degrees".to_string(),
+                                    content: "15 degrees".into(),
+                                    is_error: None,
+                                    cache_control: None,
                                 }),
                             ]),
                         },
@@ -1306,6 +1705,105 @@ mod tests {
                     ..Default::default()
                 },
             ),
+            (
+                "tool choice",
+                r#"{
+                "model": "claude-3-opus-20240229",
+                "max_tokens": 1024,
+                "tools": [{
+                    "name": "get_weather",
+                    "input_schema": {"type": "object", "properties": {}}
+                }],
+                "tool_choice": {"type": "tool", "name": "get_weather", "disable_parallel_tool_use": true},
+                "messages": [{"role": "user", "content": "What is the weather like in San Francisco?"}]
+            }"#,
+                Request {
+                    model: "claude-3-opus-20240229".to_string(),
+                    max_tokens: 1024,
+                    messages: vec![Message {
+                        role: Role::User,
+                        content: MessageContent::Text(
+                            "What is the weather like in San Francisco?".to_string(),
+                        ),
+                    }],
+                    tools: Some(vec![Tool {
+                        name: "get_weather".into(),
+                        description: None,
+                        input_schema: serde_json::json!({"type": "object", "properties": {}}),
+                        cache_control: None,
+                    }]),
+                    tool_choice: Some(ToolChoice::Tool {
+                        name: "get_weather".to_string(),
+                        disable_parallel_tool_use: Some(true),
+                    }),
+                    ..Default::default()
+                },
+            ),
+            (
+                "tool result error",
+                r#"{
+                "model": "claude-3-opus-20240229",
+                "max_tokens": 1024,
+                "tool_choice": {"type": "any"},
+                "messages": [
+                    {
+                        "role": "user",
+                        "content": [
+                            {
+                                "type": "tool_result",
+                                "tool_use_id": "toolu_01A09q90qw90lq917835lq9",
+                                "content": [
+                                    {"type": "text", "text": "location not found"},
+                                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="}}
+                                ],
+                                "is_error": true
+                            },
+                            {
+                                "type": "tool_result",
+                                "tool_use_id": "toolu_01A09q90qw90lq917835lqa"
+                            }
+                        ]
+                    }
+                ]
+            }"#,
+                Request {
+                    model: "claude-3-opus-20240229".to_string(),
+                    max_tokens: 1024,
+                    tool_choice: Some(ToolChoice::Any {
+                        disable_parallel_tool_use: None,
+                    }),
+                    messages: vec![Message {
+                        role: Role::User,
+                        content: MessageContent::Blocks(vec![
+                            ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult {
+                                tool_use_id: "toolu_01A09q90qw90lq917835lq9".to_string(),
+                                content: ToolResultContent::Blocks(vec![
+                                    ContentBlock::Base(BaseContentBlock::Text {
+                                        text: "location not found".to_string(),
+                                        cache_control: None,
+                                    }),
+                                    ContentBlock::RequestOnly(RequestOnlyContentBlock::Image {
+                                        source: ImageSource::Base64 {
+                                            media_type: "image/png".to_string(),
+                                            data: "iVBORw0KGgo=".to_string(),
+                                        },
+                                        cache_control: None,
+                                    }),
+                                ]),
+                                is_error: Some(true),
+                                cache_control: None,
+                            }),
+                            ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult {
+                                tool_use_id: "toolu_01A09q90qw90lq917835lqa".to_string(),
+                                content: ToolResultContent::default(),
+                                is_error: None,
+                                cache_control: None,
+                            }),
+                        ]),
+                    }],
+                    ..Default::default()
+                },
+            ),
         ];
         for (name, json, expected) in tests {
             //test deserialize
@@ -19,7 +19,9 @@ pub struct Response {
 #[cfg(test)]
 mod tests {
     use crate::messages::{
-        BaseContentBlock, RedactedThinkingContentBlock, Role, ToolUseContentBlock,
+        BaseContentBlock, ContentBlock, ImageSource, RedactedThinkingContentBlock,
+        RequestOnlyContentBlock, Role, ServerToolResultContentBlock, ToolResultContent,
+        ToolUseContentBlock,
     };
 
     use super::*;
@@ -52,6 +54,7 @@ mod tests {
                     content: vec![
                         ResponseContentBlock::Base(BaseContentBlock::Text {
                             text: "Hi! My name is Claude.".to_string(),
+                            cache_control: None,
                         }),
                     ],
                     role: Role::Assistant,
@@ -61,6 +64,7 @@ mod tests {
                     usage: Usage {
                         input_tokens: Some(10),
                         output_tokens: 25,
+                        ..Default::default()
                     },
                 },
             ),
@@ -96,6 +100,7 @@ mod tests {
                     content: vec![
                         ResponseContentBlock::Base(BaseContentBlock::Text {
                             text: "<thinking>I need to call the get_weather function, and the user wants SF, which is likely San Francisco, CA.</thinking>".to_string(),
+                            cache_control: None,
                         }),
                         ResponseContentBlock::Base(BaseContentBlock::ToolUse(ToolUseContentBlock {
                             id: "toolu_01A09q90qw90lq917835lq9".to_string(),
@@ -113,6 +118,7 @@ mod tests {
                     usage: Usage {
                         input_tokens: Some(527),
                         output_tokens: 137,
+                        ..Default::default()
                     },
                 },
             ),
@@ -158,6 +164,7 @@ mod tests {
                         }),
                         ResponseContentBlock::Base(BaseContentBlock::Text {
                             text: "Based on my analysis...".to_string(),
+                            cache_control: None,
                         }),
                     ],
                     role: Role::Assistant,
@@ -167,6 +174,85 @@ mod tests {
                     usage: Usage {
                         input_tokens: Some(320),
                         output_tokens: 150,
+                        ..Default::default()
+                    },
+                },
+            ),
+            (
+                "mcp_tool_result",
+                r#"{
+                  "content": [
+                    {
+                      "type": "mcp_tool_result",
+                      "tool_use_id": "mcptoolu_01",
+                      "is_error": true,
+                      "content": "connection refused"
+                    },
+                    {
+                      "type": "mcp_tool_result",
+                      "tool_use_id": "mcptoolu_02",
+                      "is_error": false,
+                      "content": [
+                        {"type": "text", "text": "Sunny"},
+                        {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo"}}
+                      ]
+                    },
+                    {
+                      "type": "mcp_tool_result",
+                      "tool_use_id": "mcptoolu_03",
+                      "content": []
+                    }
+                  ],
+                  "id": "msg_01Mcp",
+                  "model": "claude-sonnet-4-20250514",
+                  "role": "assistant",
+                  "stop_reason": "end_turn",
+                  "type": "message",
+                  "usage": {
+                    "input_tokens": 30,
+                    "output_tokens": 12
+                  }
+                }"#,
+                Response {
+                    id: "msg_01Mcp".to_string(),
+                    model: "claude-sonnet-4-20250514".to_string(),
+                    content: vec![
+                        ResponseContentBlock::ServerToolResult(ServerToolResultContentBlock::McpToolResult {
+                            tool_use_id: "mcptoolu_01".to_string(),
+                            content: ToolResultContent::Text("connection refused".to_string()),
+                            is_error: Some(true),
+                        }),
+                        ResponseContentBlock::ServerToolResult(ServerToolResultContentBlock::McpToolResult {
+                            tool_use_id: "mcptoolu_02".to_string(),
+                            content: ToolResultContent::Blocks(vec![
+                                ContentBlock::Base(BaseContentBlock::Text {
+                                    text: "Sunny".to_string(),
+                                    cache_control: None,
+                                }),
+                                ContentBlock::RequestOnly(RequestOnlyContentBlock::Image {
+                                    source: ImageSource::Base64 {
+                                        media_type: "image/png".to_string(),
+                                        data: "iVBORw0KGgo".to_string(),
+                                    },
+                                    cache_control: None,
+                                }),
+                            ]),
+                            is_error: Some(false),
+                        }),
+                        ResponseContentBlock::ServerToolResult(ServerToolResultContentBlock::McpToolResult {
+                            tool_use_id: "mcptoolu_03".to_string(),
+                            content: ToolResultContent::Blocks(vec![]),
+                            is_error: None,
+                        }),
+                    ],
+                    role: Role::Assistant,
+                    stop_reason: Some(StopReason::EndTurn),
+                    stop_sequence: None,
+                    r#type: "message".to_string(),
+                    usage: Usage {
+                        input_tokens: Some(30),
+                        output_tokens: 12,
+                        ..Default::default()
                     },
                 },
             ),
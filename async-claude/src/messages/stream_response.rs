@@ -6,7 +6,7 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
-use super::{response::Response, BaseContentBlock, DeltaContentBlock, StopReason, Usage};
+use super::{response::Response, DeltaContentBlock, ResponseContentBlock, StopReason, Usage};
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -39,6 +39,29 @@ impl FromStr for EventName {
     }
 }
 
+impl EventName {
+    /// The name used in the `event` field of the stream.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventName::Unspecified => "unspecified",
+            EventName::Error => "error",
+            EventName::MessageStart => "message_start",
+            EventName::ContentBlockDelta => "content_block_delta",
+            EventName::ContentBlockStart => "content_block_start",
+            EventName::Ping => "ping",
+            EventName::ContentBlockStop => "content_block_stop",
+            EventName::MessageDelta => "message_delta",
+            EventName::MessageStop => "message_stop",
+        }
+    }
+}
+
+impl Display for EventName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum EventData {
@@ -50,7 +73,7 @@ pub enum EventData {
     },
     ContentBlockStart {
         index: u32,
-        content_block: BaseContentBlock,
+        content_block: ResponseContentBlock,
     },
     Ping,
     ContentBlockDelta {
@@ -65,16 +88,68 @@ pub enum EventData {
         usage: Usage,
     },
     MessageStop,
+    /// An event type this crate doesn't know yet, it should be ignored.
+    #[serde(other)]
+    Unknown,
+}
+
+impl EventData {
+    pub fn event_name(&self) -> EventName {
+        match self {
+            EventData::Error { .. } => EventName::Error,
+            EventData::MessageStart { .. } => EventName::MessageStart,
+            EventData::ContentBlockStart { .. } => EventName::ContentBlockStart,
+            EventData::Ping => EventName::Ping,
+            EventData::ContentBlockDelta { .. } => EventName::ContentBlockDelta,
+            EventData::ContentBlockStop { .. } => EventName::ContentBlockStop,
+            EventData::MessageDelta { .. } => EventName::MessageDelta,
+            EventData::MessageStop => EventName::MessageStop,
+            EventData::Unknown => EventName::Unspecified,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ErrorData {
-    OverloadedError { message: String },
+    OverloadedError {
+        message: String,
+    },
     // Additional error types
-    InternalServerError { message: String },
-    BadRequestError { message: String },
-    UnauthorizedError { message: String },
+    InternalServerError {
+        message: String,
+    },
+    BadRequestError {
+        message: String,
+    },
+    UnauthorizedError {
+        message: String,
+    },
+    AuthenticationError {
+        message: String,
+    },
+    PermissionError {
+        message: String,
+    },
+    NotFoundError {
+        message: String,
+    },
+    #[serde(rename = "request_too_large")]
+    RequestTooLarge {
+        message: String,
+    },
+    RateLimitError {
+        message: String,
+    },
+    ApiError {
+        message: String,
+    },
+    /// An error type this crate doesn't know yet.
+    #[serde(untagged)]
+    Other {
+        r#type: String,
+        message: String,
+    },
 }
 
 impl Display for ErrorData {
@@ -86,6 +161,15 @@ impl Display for ErrorData {
             }
             ErrorData::BadRequestError { message } => write!(f, "BadRequestError: {}", message),
             ErrorData::UnauthorizedError { message } => write!(f, "UnauthorizedError: {}", message),
+            ErrorData::AuthenticationError { message } => {
+                write!(f, "AuthenticationError: {}", message)
+            }
+            ErrorData::PermissionError { message } => write!(f, "PermissionError: {}", message),
+            ErrorData::NotFoundError { message } => write!(f, "NotFoundError: {}", message),
+            ErrorData::RequestTooLarge { message } => write!(f, "RequestTooLarge: {}", message),
+            ErrorData::RateLimitError { message } => write!(f, "RateLimitError: {}", message),
+            ErrorData::ApiError { message } => write!(f, "ApiError: {}", message),
+            ErrorData::Other { r#type, message } => write!(f, "{}: {}", r#type, message),
         }
     }
 }
@@ -99,7 +183,10 @@ pub struct MessageDelta {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::messages::{Role, ToolUseContentBlock};
+    use crate::messages::{
+        BaseContentBlock, ContentBlock, RedactedThinkingContentBlock, Role,
+        ServerToolResultContentBlock, ToolResultContent, ToolUseContentBlock,
+    };
     #[test]
     fn serde() {
         let tests = vec![
@@ -131,6 +218,7 @@ mod tests {
                         usage: Usage {
                             input_tokens: Some(10),
                             output_tokens: 1,
+                            ..Default::default()
                         },
                     },
                 },
@@ -142,9 +230,10 @@ mod tests {
                 EventName::ContentBlockStart,
                 EventData::ContentBlockStart {
                     index: 0,
-                    content_block: BaseContentBlock::Text {
+                    content_block: ResponseContentBlock::Base(BaseContentBlock::Text {
                         text: "".to_string(),
-                    },
+                        cache_control: None,
+                    }),
                 },
             ),
             (
@@ -198,6 +287,7 @@ mod tests {
                     usage: Usage {
                         input_tokens: None,
                         output_tokens: 12,
+                        ..Default::default()
                     },
                 },
             ),
@@ -216,11 +306,13 @@ mod tests {
                 EventName::ContentBlockStart,
                 EventData::ContentBlockStart {
                     index: 1,
-                    content_block: BaseContentBlock::ToolUse(ToolUseContentBlock {
-                        id: "tu_01AbCdEfGhIjKlMnOpQrStUv".to_string(),
-                        name: "weather_forecast".to_string(),
-                        input: serde_json::json!({}),
-                    }),
+                    content_block: ResponseContentBlock::Base(BaseContentBlock::ToolUse(
+                        ToolUseContentBlock {
+                            id: "tu_01AbCdEfGhIjKlMnOpQrStUv".to_string(),
+                            name: "weather_forecast".to_string(),
+                            input: serde_json::json!({}),
+                        },
+                    )),
                 },
             ),
             (
@@ -254,10 +346,10 @@ mod tests {
                 EventName::ContentBlockStart,
                 EventData::ContentBlockStart {
                     index: 2,
-                    content_block: BaseContentBlock::Thinking {
+                    content_block: ResponseContentBlock::Base(BaseContentBlock::Thinking {
                         thinking: "".to_string(),
                         signature: None,
-                    },
+                    }),
                 },
             ),
             (
@@ -299,6 +391,7 @@ mod tests {
                     usage: Usage {
                         input_tokens: None,
                         output_tokens: 1024,
+                        ..Default::default()
                     },
                 },
             ),
@@ -315,6 +408,7 @@ mod tests {
                     usage: Usage {
                         input_tokens: None,
                         output_tokens: 45,
+                        ..Default::default()
                     },
                 },
             ),
@@ -325,11 +419,129 @@ mod tests {
                 EventName::ContentBlockStart,
                 EventData::ContentBlockStart {
                     index: 1,
-                    content_block: BaseContentBlock::ToolUse(ToolUseContentBlock {
-                        id: "toolu_01T1x1fJ34qAmk2tNTrN7Up6".to_string(),
-                        name: "get_weather".to_string(),
-                        input: serde_json::json!({}),
-                    }),
+                    content_block: ResponseContentBlock::Base(BaseContentBlock::ToolUse(
+                        ToolUseContentBlock {
+                            id: "toolu_01T1x1fJ34qAmk2tNTrN7Up6".to_string(),
+                            name: "get_weather".to_string(),
+                            input: serde_json::json!({}),
+                        },
+                    )),
+                },
+            ),
+            (
+                "error_request_too_large",
+                "error",
+                r#"{"type": "error", "error": {"type": "request_too_large", "message": "Request exceeds the maximum allowed number of bytes."}}"#,
+                EventName::Error,
+                EventData::Error {
+                    error: ErrorData::RequestTooLarge {
+                        message: "Request exceeds the maximum allowed number of bytes.".to_string(),
+                    },
+                },
+            ),
+            (
+                "error_unknown",
+                "error",
+                r#"{"type": "error", "error": {"type": "billing_error", "message": "Credit balance is too low"}}"#,
+                EventName::Error,
+                EventData::Error {
+                    error: ErrorData::Other {
+                        r#type: "billing_error".to_string(),
+                        message: "Credit balance is too low".to_string(),
+                    },
+                },
+            ),
+            (
+                "message_delta_refusal",
+                "message_delta",
+                r#"{"type":"message_delta","delta":{"stop_reason":"refusal","stop_sequence":null},"usage":{"output_tokens":3}}"#,
+                EventName::MessageDelta,
+                EventData::MessageDelta {
+                    delta: MessageDelta {
+                        stop_reason: StopReason::Refusal,
+                        stop_sequence: None,
+                    },
+                    usage: Usage {
+                        output_tokens: 3,
+                        ..Default::default()
+                    },
+                },
+            ),
+            (
+                "message_delta_unknown_stop_reason",
+                "message_delta",
+                r#"{"type":"message_delta","delta":{"stop_reason":"model_context_window_exceeded","stop_sequence":null},"usage":{"output_tokens":3}}"#,
+                EventName::MessageDelta,
+                EventData::MessageDelta {
+                    delta: MessageDelta {
+                        stop_reason: StopReason::Unknown,
+                        stop_sequence: None,
+                    },
+                    usage: Usage {
+                        output_tokens: 3,
+                        ..Default::default()
+                    },
+                },
+            ),
+            (
+                "content_block_delta_unknown",
+                "content_block_delta",
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"citations_delta","citation":{"type":"char_location","cited_text":"Paris"}}}"#,
+                EventName::ContentBlockDelta,
+                EventData::ContentBlockDelta {
+                    index: 0,
+                    delta: DeltaContentBlock::Unknown,
+                },
+            ),
+            (
+                "content_block_start_redacted_thinking",
+                "content_block_start",
+                r#"{"type":"content_block_start","index":1,"content_block":{"type":"redacted_thinking","data":"EmwKAhgBEgy3va3pzix"}}"#,
+                EventName::ContentBlockStart,
+                EventData::ContentBlockStart {
+                    index: 1,
+                    content_block: ResponseContentBlock::RedactedThinking(
+                        RedactedThinkingContentBlock::RedactedThinking {
+                            data: "EmwKAhgBEgy3va3pzix".to_string(),
+                        },
+                    ),
+                },
+            ),
+            (
+                "content_block_start_mcp_tool_result_error",
+                "content_block_start",
+                r#"{"type":"content_block_start","index":2,"content_block":{"type":"mcp_tool_result","tool_use_id":"mcptoolu_01","is_error":true,"content":"connection refused"}}"#,
+                EventName::ContentBlockStart,
+                EventData::ContentBlockStart {
+                    index: 2,
+                    content_block: ResponseContentBlock::ServerToolResult(
+                        ServerToolResultContentBlock::McpToolResult {
+                            tool_use_id: "mcptoolu_01".to_string(),
+                            content: ToolResultContent::Text("connection refused".to_string()),
+                            is_error: Some(true),
+                        },
+                    ),
+                },
+            ),
+            (
+                "content_block_start_mcp_tool_result_blocks",
+                "content_block_start",
+                r#"{"type":"content_block_start","index":3,"content_block":{"type":"mcp_tool_result","tool_use_id":"mcptoolu_02","is_error":false,"content":[{"type":"text","text":"Sunny"}]}}"#,
+                EventName::ContentBlockStart,
+                EventData::ContentBlockStart {
+                    index: 3,
+                    content_block: ResponseContentBlock::ServerToolResult(
+                        ServerToolResultContentBlock::McpToolResult {
+                            tool_use_id: "mcptoolu_02".to_string(),
+                            content: ToolResultContent::Blocks(vec![
+                                ContentBlock::Base(BaseContentBlock::Text {
+                                    text: "Sunny".to_string(),
+                                    cache_control: None,
+                                }),
+                            ]),
+                            is_error: Some(false),
+                        },
+                    ),
                 },
             ),
         ];
@@ -347,6 +559,25 @@ mod tests {
                 "test failed for event data: {}",
                 test_name
             );
+            let serialized = serde_json::to_string(&event_data).unwrap();
+            let got_event_data: EventData = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(
+                got_event_data, event_data,
+                "serialize test failed for event data: {}",
+                test_name
+            );
+            assert_eq!(
+                got_event_data.event_name(),
+                event_name,
+                "test failed for event data name: {}",
+                test_name
+            );
+            assert_eq!(
+                event_name.as_str(),
+                name,
+                "test failed for as_str: {}",
+                test_name
+            );
         }
     }
 }
@@ -7,7 +7,10 @@ pub fn price(model: &str, usage: &Usage) -> f32 {
         "claude-3-haiku-20240307" => (0.015, 0.075),
         _ => return 0.0,
     };
+    // Cache writes cost 1.25 times the input price, cache reads 0.1 times
     let price = usage.input_tokens.unwrap_or_default() as f32 * prompt_price
+        + usage.cache_creation_input_tokens.unwrap_or_default() as f32 * prompt_price * 1.25
+        + usage.cache_read_input_tokens.unwrap_or_default() as f32 * prompt_price * 0.1
         + usage.output_tokens as f32 * completion_price;
     price / 1000.0
 }
@@ -73,6 +73,7 @@ where
         name: name.into(),
         description: desc.map(Into::into),
         input_schema: json_value,
+        cache_control: None,
     })
 }
 
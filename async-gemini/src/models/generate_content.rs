@@ -44,31 +44,120 @@ where
 /// For gemini-1.0-pro, only the text field is valid. The token limit is 32k.
 /// For gemini-1.0-pro-vision, you may specify either text only, text and up to 16 images, or text and 1 video. The token limit is 16k.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(from = "RawPart", into = "RawPart")]
 pub enum Part {
     /// The text instructions or chat dialogue to include in the prompt.
-    #[serde(rename = "text")]
     Text(String),
+    /// A thought summary of the model, only returned when `include_thoughts` is set.
+    Thought(String),
     /// Serialized bytes data of the image or video. You can specify at most 1 image with inlineData. To specify up to 16 images, use fileData.
-    #[serde(rename = "inlineData")]
     Inline(InlineData),
-    #[serde(rename = "functionCall")]
     FunctionCall(FunctionCall),
-    #[serde(rename = "functionResponse")]
     FunctionResponse(FunctionResponse),
-    #[serde(rename = "fileData")]
     File(FileData),
+    /// A part this crate doesn't know yet, e.g. `executableCode`, kept as its raw fields.
+    Unknown(serde_json::Map<String, serde_json::Value>),
 }
 
 impl Part {
     pub fn is_empty(&self) -> bool {
         match self {
-            Part::Text(s) => s.trim().is_empty(),
+            Part::Text(s) | Part::Thought(s) => s.trim().is_empty(),
             Part::Inline(data) => data.data.trim().is_empty(),
             Part::FunctionCall(call) => call.name.trim().is_empty(),
             Part::FunctionResponse(response) => {
                 response.name.trim().is_empty() || response.response.is_null()
             }
             Part::File(file) => file.file_uri.trim().is_empty() || file.mime_type.trim().is_empty(),
+            Part::Unknown(fields) => fields.is_empty(),
+        }
+    }
+}
+
+/// The wire format of a part, the data field is a union and thoughts are text flagged with `thought`.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct RawPart {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    thought: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inline_data: Option<InlineData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_call: Option<FunctionCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_response: Option<FunctionResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_data: Option<FileData>,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl From<RawPart> for Part {
+    fn from(raw: RawPart) -> Self {
+        match raw {
+            RawPart {
+                text: Some(text),
+                thought: true,
+                ..
+            } => Part::Thought(text),
+            RawPart {
+                text: Some(text), ..
+            } => Part::Text(text),
+            RawPart {
+                inline_data: Some(data),
+                ..
+            } => Part::Inline(data),
+            RawPart {
+                function_call: Some(call),
+                ..
+            } => Part::FunctionCall(call),
+            RawPart {
+                function_response: Some(response),
+                ..
+            } => Part::FunctionResponse(response),
+            RawPart {
+                file_data: Some(data),
+                ..
+            } => Part::File(data),
+            RawPart { other, .. } => Part::Unknown(other),
+        }
+    }
+}
+
+impl From<Part> for RawPart {
+    fn from(part: Part) -> Self {
+        match part {
+            Part::Text(text) => RawPart {
+                text: Some(text),
+                ..Default::default()
+            },
+            Part::Thought(text) => RawPart {
+                text: Some(text),
+                thought: true,
+                ..Default::default()
+            },
+            Part::Inline(data) => RawPart {
+                inline_data: Some(data),
+                ..Default::default()
+            },
+            Part::FunctionCall(call) => RawPart {
+                function_call: Some(call),
+                ..Default::default()
+            },
+            Part::FunctionResponse(response) => RawPart {
+                function_response: Some(response),
+                ..Default::default()
+            },
+            Part::File(data) => RawPart {
+                file_data: Some(data),
+                ..Default::default()
+            },
+            Part::Unknown(other) => RawPart {
+                other,
+                ..Default::default()
+            },
         }
     }
 }
@@ -112,8 +201,9 @@ pub struct FunctionCall {
 /// Required. The name of the function to call. Must be a-z, A-Z, 0-9, or contain underscores and dashes, with a maximum length of 63.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct FunctionResponse {
-    name: String,
-    response: serde_json::Value,
+    pub name: String,
+    /// The function response in JSON object format.
+    pub response: serde_json::Value,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
@@ -23,7 +23,13 @@ pub struct GenerateContentRequest {
     #[serde(deserialize_with = "deserialize_option_obj_or_vec", default)]
     pub safety_settings: Option<Vec<SafetySetting>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub generation_config: Option<GenerateionConfig>,
+    pub generation_config: Option<GenerationConfig>,
+    /// Developer set system instructions. Currently, text only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_instruction: Option<Content>,
+    /// Configuration for any Tool specified in the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_config: Option<ToolConfig>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -34,24 +40,84 @@ pub struct Tool {
     /// description (optional). The description and purpose of the function. The model uses this to decide how and whether to call the function. For the best results, we recommend that you include a description.
     /// parameters The parameters of this function in a format that's compatible with the OpenAPI schema format.
     /// For more information, see Function calling.
-    function_declarations: Vec<FunctionTool>,
+    pub function_declarations: Vec<FunctionTool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct FunctionTool {
-    name: String,
-    description: Option<String>,
-    parameters: serde_json::Value,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Omitted for a function without parameters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+impl FunctionTool {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            parameters: None,
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// The parameters in the OpenAPI schema format.
+    pub fn parameters(mut self, parameters: serde_json::Value) -> Self {
+        self.parameters = Some(parameters);
+        self
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolConfig {
+    pub function_calling_config: FunctionCallingConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionCallingConfig {
+    pub mode: FunctionCallingMode,
+    /// The functions the model may call, only allowed with mode `ANY`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_function_names: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FunctionCallingMode {
+    /// The model decides to predict either a function call or a natural language response.
+    #[default]
+    Auto,
+    /// The model is constrained to always predict a function call.
+    Any,
+    /// The model will not predict any function call.
+    None,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub struct SafetySetting {
-    category: HarmCategory,
-    threshold: SafetySettingThreshold,
+    pub category: HarmCategory,
+    pub threshold: SafetySettingThreshold,
+}
+
+impl SafetySetting {
+    pub fn new(category: HarmCategory, threshold: SafetySettingThreshold) -> Self {
+        Self {
+            category,
+            threshold,
+        }
+    }
 }
 
 /// The threshold for blocking responses that could belong to the specified safety category based on probability.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum SafetySettingThreshold {
     BlockNone,
@@ -60,9 +126,12 @@ pub enum SafetySettingThreshold {
     BlockOnlyHigh,
 }
 
+#[deprecated(note = "use `GenerationConfig`")]
+pub type GenerateionConfig = GenerationConfig;
+
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct GenerateionConfig {
+pub struct GenerationConfig {
     /// The temperature is used for sampling during the response generation, which occurs when topP and topK are applied. Temperature controls the degree of randomness in token selection. Lower temperatures are good for prompts that require a more deterministic and less open-ended or creative response, while higher temperatures can lead to more diverse or creative results. A temperature of 0 is deterministic: the highest probability response is always selected.
     /// Range: 0.0 - 1.0
     /// Default for gemini-1.0-pro: 0.9
@@ -95,6 +164,59 @@ pub struct GenerateionConfig {
     /// public static string
     /// Maximum 5 items in the list.
     pub stop_sequences: Option<Vec<String>>,
+    /// MIME type of the generated candidate text, `text/plain` (default), `application/json` or `text/x.enum`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_mime_type: Option<String>,
+    /// Output schema of the generated candidate text, a subset of the OpenAPI schema object.
+    /// Requires a compatible `response_mime_type`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<serde_json::Value>,
+    /// Output schema of the generated response in JSON Schema, an alternative to `response_schema`.
+    /// Requires a compatible `response_mime_type`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_json_schema: Option<serde_json::Value>,
+    /// Penalizes tokens that already appear in the generated text, regardless of how often.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    /// Penalizes tokens proportionally to the number of times they appear in the generated text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    /// Seed used in decoding. If not set, the request uses a randomly generated seed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i32>,
+    /// If true, export the logprobs results in the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_logprobs: Option<bool>,
+    /// The number of top logprobs to return at each decoding step, only valid with `response_logprobs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_config: Option<ThinkingConfig>,
+    /// The modalities of the response, e.g. `[TEXT, IMAGE]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_modalities: Option<Vec<Modality>>,
+}
+
+/// Config for the thinking features of the model.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ThinkingConfig {
+    /// The number of thinking tokens the model may use, `0` disables thinking and `-1` lets the model decide.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<i32>,
+    /// Whether to include thought summaries in the response, returned as `Part::Thought`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_thoughts: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Modality {
+    #[serde(rename = "MODALITY_UNSPECIFIED")]
+    Unspecified,
+    Text,
+    Image,
+    Audio,
 }
 
 /// Gemini require contents:
@@ -202,7 +324,7 @@ mod tests {
                         category: HarmCategory::SexuallyExplicit,
                         threshold: SafetySettingThreshold::BlockLowAndAbove,
                     }]),
-                    generation_config: Some(GenerateionConfig {
+                    generation_config: Some(GenerationConfig {
                         temperature: Some(0.2),
                         top_p: Some(0.8),
                         top_k: Some(40),
@@ -264,7 +386,7 @@ mod tests {
                         category: HarmCategory::SexuallyExplicit,
                         threshold: SafetySettingThreshold::BlockLowAndAbove,
                     }]),
-                    generation_config: Some(GenerateionConfig {
+                    generation_config: Some(GenerationConfig {
                         temperature: Some(0.2),
                         top_p: Some(0.8),
                         top_k: Some(40),
@@ -320,7 +442,7 @@ mod tests {
                         category: HarmCategory::SexuallyExplicit,
                         threshold: SafetySettingThreshold::BlockLowAndAbove,
                     }]),
-                    generation_config: Some(GenerateionConfig {
+                    generation_config: Some(GenerationConfig {
                         temperature: Some(0.4),
                         top_p: Some(1.0),
                         top_k: Some(32),
@@ -431,7 +553,7 @@ mod tests {
                             FunctionTool{
                                 name:"find_movies".to_string(),
                                 description:Some("find movie titles currently playing in theaters based on any description, genre, title words, etc.".to_string()),
-                                parameters:json!({
+                                parameters:Some(json!({
                               "type": "object",
                               "properties": {
                                 "location": {
@@ -446,12 +568,12 @@ mod tests {
                               "required": [
                                 "description"
                               ]
-                            })
+                            }))
                             },
                             FunctionTool{
                                 name:"find_theaters".to_string(),
                                 description:Some("find theaters based on location and optionally movie title which are is currently playing in theaters".to_string()),
-                                parameters:json!({
+                                parameters:Some(json!({
                               "type": "object",
                               "properties": {
                                 "location": {
@@ -466,12 +588,12 @@ mod tests {
                               "required": [
                                 "location"
                               ]
-                            })
+                            }))
                             },
                             FunctionTool{
                                 name:"get_showtimes".to_string(),
                                 description:Some("Find the start times for movies playing in a specific theater".to_string()),
-                                parameters:json!({
+                                parameters:Some(json!({
                               "type": "object",
                               "properties": {
                                 "location": {
@@ -497,13 +619,77 @@ mod tests {
                                 "theater",
                                 "date"
                               ]
-                            })
+                            }))
                             }
                         ]
                     }]),
                     ..Default::default()
                 },
             ),
+            (
+                "system instruction and tool config",
+                r#"{
+                    "systemInstruction": {"role": "user", "parts": [{"text": "You are a cat."}]},
+                    "contents": [{"role": "user", "parts": [{"text": "What time is it?"}]}],
+                    "tools": [{"functionDeclarations": [{"name": "get_time"}]}],
+                    "toolConfig": {"functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["get_time"]}}
+                }"#,
+                GenerateContentRequest {
+                    system_instruction: Some(Content {
+                        role: Role::User,
+                        parts: vec![Part::Text("You are a cat.".to_string())],
+                    }),
+                    contents: vec![Content {
+                        role: Role::User,
+                        parts: vec![Part::Text("What time is it?".to_string())],
+                    }],
+                    tools: Some(vec![Tool {
+                        function_declarations: vec![FunctionTool {
+                            name: "get_time".to_string(),
+                            description: None,
+                            parameters: None,
+                        }],
+                    }]),
+                    tool_config: Some(ToolConfig {
+                        function_calling_config: FunctionCallingConfig {
+                            mode: FunctionCallingMode::Any,
+                            allowed_function_names: Some(vec!["get_time".to_string()]),
+                        },
+                    }),
+                    ..Default::default()
+                },
+            ),
+            (
+                "structured output and thinking",
+                r#"{
+                    "contents": [{"role": "user", "parts": [{"text": "List a few cookies."}]}],
+                    "generationConfig": {
+                        "responseMimeType": "application/json",
+                        "responseJsonSchema": {"type": "array", "items": {"type": "string"}},
+                        "seed": 7,
+                        "thinkingConfig": {"thinkingBudget": 1024, "includeThoughts": true},
+                        "responseModalities": ["TEXT"]
+                    }
+                }"#,
+                GenerateContentRequest {
+                    contents: vec![Content {
+                        role: Role::User,
+                        parts: vec![Part::Text("List a few cookies.".to_string())],
+                    }],
+                    generation_config: Some(GenerationConfig {
+                        response_mime_type: Some("application/json".to_string()),
+                        response_json_schema: Some(json!({"type": "array", "items": {"type": "string"}})),
+                        seed: Some(7),
+                        thinking_config: Some(ThinkingConfig {
+                            thinking_budget: Some(1024),
+                            include_thoughts: Some(true),
+                        }),
+                        response_modalities: Some(vec![Modality::Text]),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            ),
         ];
         for (name, json, expected) in tests {
             //test deserialize
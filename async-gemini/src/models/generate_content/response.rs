@@ -1,29 +1,84 @@
 use serde::{Deserialize, Serialize};
 
-use super::{Content, HarmCategory};
+use super::{Content, FunctionCall, HarmCategory, Part};
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerateContentResponse {
     /// Candidate responses from the model.
-    candidates: Vec<Candidate>,
-    prompt_feedback: Option<PromptFeedback>,
+    #[serde(default)]
+    pub candidates: Vec<Candidate>,
+    pub prompt_feedback: Option<PromptFeedback>,
+    /// Token usage of the request. In a stream every chunk carries the running count.
+    pub usage_metadata: Option<UsageMetadata>,
+    pub model_version: Option<String>,
+    pub response_id: Option<String>,
+}
+
+impl GenerateContentResponse {
+    pub fn first_candidate(&self) -> Option<&Candidate> {
+        self.candidates.first()
+    }
+
+    /// The text of the first candidate, `None` if it has no text part.
+    pub fn text(&self) -> Option<String> {
+        self.first_candidate().and_then(Candidate::text)
+    }
+
+    /// The function calls of the first candidate.
+    pub fn function_calls(&self) -> Vec<&FunctionCall> {
+        self.first_candidate()
+            .map(Candidate::function_calls)
+            .unwrap_or_default()
+    }
+}
+
+impl Candidate {
+    /// Concatenates the text parts, `None` if there is none.
+    pub fn text(&self) -> Option<String> {
+        let mut texts = self
+            .content
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .peekable();
+        texts.peek()?;
+        Some(texts.collect())
+    }
+
+    pub fn function_calls(&self) -> Vec<&FunctionCall> {
+        self.content
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::FunctionCall(call) => Some(call),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 /// A response candidate generated from the model.
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Candidate {
-    content: Content,
+    /// Absent when the candidate is blocked before any content is generated.
+    #[serde(default)]
+    pub content: Content,
     #[serde(default)]
-    finish_reason: Option<FinishReason>,
+    pub finish_reason: Option<FinishReason>,
     /// List of ratings for the safety of a response candidate.
     /// There is at most one rating per category.
-    safety_ratings: Vec<SafetyRating>,
+    #[serde(default)]
+    pub safety_ratings: Vec<SafetyRating>,
     /// Citation information for model-generated candidate.
     /// This field may be populated with recitation information for any text included in the content. These are passages that are "recited" from copyrighted material in the foundational LLM's training data.
-    citation_metadata: Option<CitationMetadata>,
-    index: u32,
+    pub citation_metadata: Option<CitationMetadata>,
+    #[serde(default)]
+    pub index: u32,
 }
 
 /// Defines the reason why the model stopped generating tokens.
@@ -43,8 +98,23 @@ pub enum FinishReason {
     Safety,
     /// The candidate content was flagged for recitation reasons.
     Recitation,
+    /// The candidate content was flagged for using an unsupported language.
+    Language,
     /// Unknown reason.
     Other,
+    /// Token generation stopped because the content contains forbidden terms.
+    Blocklist,
+    /// Token generation stopped for potentially containing prohibited content.
+    ProhibitedContent,
+    /// Token generation stopped because the content potentially contains Sensitive Personally Identifiable Information.
+    Spii,
+    /// The function call generated by the model is invalid.
+    MalformedFunctionCall,
+    /// Token generation stopped because generated images contain safety violations.
+    ImageSafety,
+    /// A reason this crate doesn't know yet.
+    #[serde(other)]
+    Unknown,
 }
 
 /// Safety rating for a piece of content.
@@ -53,11 +123,11 @@ pub enum FinishReason {
 #[serde(rename_all = "camelCase")]
 pub struct SafetyRating {
     /// The category for this rating.
-    category: HarmCategory,
+    pub category: HarmCategory,
     /// The probability of harm for this content.
-    probability: HarmProbability,
+    pub probability: HarmProbability,
     /// Was this content blocked because of this rating?
-    blocked: Option<bool>,
+    pub blocked: Option<bool>,
 }
 
 /// The probability that a piece of content is harmful.
@@ -82,33 +152,33 @@ pub enum HarmProbability {
 #[serde(rename_all = "camelCase")]
 pub struct CitationMetadata {
     /// Citations to sources for a specific response.
-    citation_sources: Vec<CitationSource>,
+    pub citation_sources: Vec<CitationSource>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct CitationSource {
-    start_index: Option<u32>,
-    end_index: Option<u32>,
-    uri: Option<String>,
-    title: Option<String>,
-    license: Option<String>,
+    pub start_index: Option<u32>,
+    pub end_index: Option<u32>,
+    pub uri: Option<String>,
+    pub title: Option<String>,
+    pub license: Option<String>,
     /// The date a citation was published. Its valid formats are YYYY, YYYY-MM, and YYYY-MM-DD.
-    publication_date: Option<PublicationDate>,
+    pub publication_date: Option<PublicationDate>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct PublicationDate {
-    year: Option<u32>,
-    month: Option<u32>,
-    day: Option<u32>,
+    pub year: Option<u32>,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct PromptFeedback {
-    block_reason: Option<BlockReason>,
-    safety_ratings: Option<Vec<SafetyRating>>,
+    pub block_reason: Option<BlockReason>,
+    pub safety_ratings: Option<Vec<SafetyRating>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -118,11 +188,31 @@ pub enum BlockReason {
     Unspecified,
     Safety,
     Other,
+    Blocklist,
+    ProhibitedContent,
+}
+
+/// Metadata on the generation request's token usage.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageMetadata {
+    /// Number of tokens in the prompt, including the cached content.
+    #[serde(default)]
+    pub prompt_token_count: u32,
+    /// Number of tokens in the cached part of the prompt.
+    pub cached_content_token_count: Option<u32>,
+    /// Total number of tokens across all the generated response candidates.
+    #[serde(default)]
+    pub candidates_token_count: u32,
+    /// Number of tokens used for thinking, not included in `candidates_token_count`.
+    pub thoughts_token_count: Option<u32>,
+    #[serde(default)]
+    pub total_token_count: u32,
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::models::{Part, Role};
+    use crate::models::Role;
 
     use super::*;
 
@@ -246,7 +336,8 @@ mod tests {
                         blocked:None,
                     },
                     ]),
-                })
+                }),
+                ..Default::default()
             },
         ),
         (
@@ -312,7 +403,59 @@ mod tests {
                             blocked:None,
                         },
                     ]),
-                })
+                }),
+                ..Default::default()
+            }
+        ),
+        (
+            "function call with usage",
+            r#"{"candidates": [{"content": {"parts": [{"functionCall": {"name": "get_weather","args": {"location": "Boston"}}}],"role": "model"},"finishReason": "STOP"}],"usageMetadata": {"promptTokenCount": 20,"candidatesTokenCount": 5,"totalTokenCount": 25},"modelVersion": "gemini-2.0-flash","responseId": "resp_1"}"#,
+            GenerateContentResponse{
+                candidates:vec![
+                    Candidate{
+                        content:Content{
+                            parts:vec![
+                                Part::FunctionCall(FunctionCall{
+                                    name:"get_weather".to_string(),
+                                    args:Some(serde_json::json!({"location": "Boston"})),
+                                })
+                            ],
+                            role:Role::Model,
+                        },
+                        finish_reason:Some(FinishReason::Stop),
+                        ..Default::default()
+                    }
+                ],
+                usage_metadata:Some(UsageMetadata{
+                    prompt_token_count:20,
+                    candidates_token_count:5,
+                    total_token_count:25,
+                    ..Default::default()
+                }),
+                model_version:Some("gemini-2.0-flash".to_string()),
+                response_id:Some("resp_1".to_string()),
+                ..Default::default()
+            }
+        ),
+        (
+            "unknown part and finish reason",
+            r#"{"candidates": [{"content": {"parts": [{"executableCode": {"language": "PYTHON","code": "print(1)"}},{"codeExecutionResult": {"outcome": "OUTCOME_OK","output": "1"}},{"text": "1"}],"role": "model"},"finishReason": "UNEXPECTED_TOOL_CALL"}]}"#,
+            GenerateContentResponse{
+                candidates:vec![
+                    Candidate{
+                        content:Content{
+                            parts:vec![
+                                Part::Unknown(serde_json::json!({"executableCode": {"language": "PYTHON","code": "print(1)"}}).as_object().unwrap().clone()),
+                                Part::Unknown(serde_json::json!({"codeExecutionResult": {"outcome": "OUTCOME_OK","output": "1"}}).as_object().unwrap().clone()),
+                                Part::Text("1".to_string()),
+                            ],
+                            role:Role::Model,
+                        },
+                        finish_reason:Some(FinishReason::Unknown),
+                        ..Default::default()
+                    }
+                ],
+                ..Default::default()
             }
         ),
         ];
@@ -326,4 +469,42 @@ mod tests {
             assert_eq!(actual, expected, "serialize test failed: {}", name);
         }
     }
+
+    #[test]
+    fn helpers() {
+        let tests = vec![
+            (
+                "text",
+                r#"{"candidates": [{"content": {"parts": [{"text": "Hello"},{"text": ", world"}],"role": "model"}}]}"#,
+                Some("Hello, world".to_string()),
+                vec![],
+            ),
+            (
+                "function calls",
+                r#"{"candidates": [{"content": {"parts": [{"functionCall": {"name": "a"}},{"functionCall": {"name": "b","args": {}}}],"role": "model"}}]}"#,
+                None,
+                vec!["a", "b"],
+            ),
+            (
+                "blocked prompt",
+                r#"{"promptFeedback": {"blockReason": "SAFETY"}}"#,
+                None,
+                vec![],
+            ),
+        ];
+        for (name, json, text, function_names) in tests {
+            let response: GenerateContentResponse = serde_json::from_str(json).unwrap();
+            assert_eq!(response.text(), text, "text test failed: {}", name);
+            let names: Vec<&str> = response
+                .function_calls()
+                .iter()
+                .map(|call| call.name.as_str())
+                .collect();
+            assert_eq!(
+                names, function_names,
+                "function calls test failed: {}",
+                name
+            );
+        }
+    }
 }
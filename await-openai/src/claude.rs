@@ -1,84 +1,154 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::Result;
 
 use crate::{
+    convert::{
+        ConversionError, ConversionIssue, ConversionMode, Converted, Reporter,
+        max_completion_tokens,
+    },
     entity::{
         chat_completion_chunk::{
-            Choice, Chunk, ChunkResponse, DeltaMessage, OpenaiEventDataParser,
+            Choice, Chunk, ChunkResponse, DeltaMessage, OpenaiEventDataParser, PartialToolCall,
         },
         chat_completion_object::{
-            Response as OpenaiResponse, Role as OpenaiRole, Usage as OpenaiUsage,
+            Choice as OpenaiResponseChoice, Err as OpenaiErr, Message as OpenaiResponseMessage,
+            PromptTokensDetails, Response as OpenaiResponse, Role as OpenaiRole,
+            Usage as OpenaiUsage,
         },
         create_chat_completion::{
-            Content, ContentPart, FinishReason, Message as OpenaiMessage,
-            RequestBody as OpenaiRequestBody, Stop, ToolCall, ToolCallFunction,
-            ToolCallFunctionObj,
+            AssistantMessage, Content, ContentPart, FinishReason, FunctionName, FunctionTool,
+            ImageContentPart, ImageUrl, Message as OpenaiMessage, ReasoningEffort,
+            RequestBody as OpenaiRequestBody, ResponseFormat, Stop,
+            SystemMessage as OpenaiSystemMessage, TextContentPart, Tool as OpenaiTool, ToolCall,
+            ToolCallFunction, ToolCallFunctionObj, ToolChoice as OpenaiToolChoice,
+            ToolChoiceFunction, ToolMessage, ToolType, UserMessage,
         },
     },
     magi::EventDataParser,
+    sse::{FromSseEvent, SseEvent, ToSseEvent},
 };
 
 pub use async_claude::messages::*;
 
+mod encoder;
+pub use encoder::OpenaiToClaudeEventEncoder;
+
 impl From<OpenaiRequestBody> for Request {
     fn from(body: OpenaiRequestBody) -> Self {
-        let mut res = Request {
-            model: body.model,
-            stream: body.stream,
-            temperature: body.temperature,
-            top_p: body.top_p,
-            max_tokens: body.max_tokens.unwrap_or(4000),
-            ..Default::default()
-        };
-        let mut messages = Vec::with_capacity(body.messages.len());
-        let mut system_message = None;
-        for message in body.messages {
-            match message {
-                OpenaiMessage::System(system) => {
-                    system_message.replace(system.content);
+        let Converted { request, report } =
+            convert_with_report(body, ConversionMode::Lossy).expect("lossy conversion never fails");
+        for issue in report.issues {
+            tracing::warn!("Claude request conversion: {}", issue);
+        }
+        request
+    }
+}
+
+/// Convert an Openai request to a Claude request and report what Claude can't express.
+/// In strict mode the first dropped or degraded field is returned as an error.
+pub fn convert_with_report(
+    body: OpenaiRequestBody,
+    mode: ConversionMode,
+) -> Result<Converted<Request>, ConversionError> {
+    let mut reporter = Reporter::new(mode);
+    report_unsupported_fields(&body, &mut reporter)?;
+    // Claude requires max_tokens, the default isn't something the caller sent
+    let max_tokens = max_completion_tokens(&body, &mut reporter)?.unwrap_or(4000);
+    let mut res = Request {
+        model: body.model,
+        stream: body.stream,
+        temperature: body.temperature,
+        top_p: body.top_p,
+        max_tokens,
+        metadata: body.user.map(|user| serde_json::json!({ "user_id": user })),
+        ..Default::default()
+    };
+    let mut messages = Vec::with_capacity(body.messages.len());
+    let mut system_message: Option<(usize, String)> = None;
+    for (i, message) in body.messages.into_iter().enumerate() {
+        match message {
+            OpenaiMessage::System(system) => {
+                if system.name.is_some() {
+                    reporter
+                        .report(ConversionIssue::dropped("name", NOT_SUPPORTED).at_message(i))?;
+                }
+                if let Some((previous, _)) = system_message.replace((i, system.content)) {
+                    reporter.report(
+                        ConversionIssue::dropped(
+                            "system",
+                            "Claude takes a single system prompt, replaced by a later one",
+                        )
+                        .at_message(previous),
+                    )?;
+                }
+            }
+            OpenaiMessage::User(user) => {
+                if user.name.is_some() {
+                    reporter
+                        .report(ConversionIssue::dropped("name", NOT_SUPPORTED).at_message(i))?;
                 }
-                OpenaiMessage::User(user) => match user.content {
+                match user.content {
                     Content::Text(text) => messages.push(Message {
                         role: Role::User,
                         content: MessageContent::Text(text),
                     }),
                     Content::Array(parts) => {
                         let mut blocks = vec![];
-                        for p in parts {
+                        for (j, p) in parts.into_iter().enumerate() {
                             match p {
                                 ContentPart::Text(text_part) => {
                                     blocks.push(ContentBlock::Base(BaseContentBlock::Text {
                                         text: text_part.text,
+                                        cache_control: None,
                                     }))
                                 }
                                 ContentPart::Image(image_part) => {
-                                    if !image_part.image_url.url.starts_with("http") {
-                                        if let Some(mime) =
-                                            parse_mime_from_base64(&image_part.image_url.url)
+                                    let url = image_part.image_url.url;
+                                    let source = match parse_mime_from_base64(&url) {
+                                        Some(mime) => Some(ImageSource::Base64 {
+                                            media_type: mime,
+                                            data: url,
+                                        }),
+                                        None if url.starts_with("http://")
+                                            || url.starts_with("https://") =>
                                         {
-                                            blocks.push(ContentBlock::RequestOnly(
-                                                RequestOnlyContentBlock::Image {
-                                                    source: ImageSource::Base64 {
-                                                        media_type: mime,
-                                                        data: image_part.image_url.url,
-                                                    },
-                                                },
-                                            ))
+                                            Some(ImageSource::Url { url })
                                         }
+                                        None => None,
+                                    };
+                                    match source {
+                                        Some(source) => blocks.push(ContentBlock::RequestOnly(
+                                            RequestOnlyContentBlock::Image {
+                                                source,
+                                                cache_control: None,
+                                            },
+                                        )),
+                                        None => reporter.report(
+                                            ConversionIssue::dropped(
+                                                "image_url",
+                                                "unsupported image type",
+                                            )
+                                            .at_message(i)
+                                            .at_part(j),
+                                        )?,
                                     }
-                                    tracing::warn!("Image URL is not supported in Claude yet");
                                 }
                                 #[cfg(feature = "custom_content_part")]
-                                ContentPart::Document(_) => {
-                                    // Claude doesn't support document content parts yet,
-                                    // so we skip them for now
-                                }
+                                ContentPart::Document(_) => reporter.report(
+                                    ConversionIssue::dropped("document", NOT_SUPPORTED)
+                                        .at_message(i)
+                                        .at_part(j),
+                                )?,
                                 #[cfg(feature = "custom_content_part")]
-                                ContentPart::Audio(_) => {
-                                    // Claude doesn't support audio content parts yet,
-                                    // so we skip them for now
-                                }
+                                ContentPart::Audio(_) => reporter.report(
+                                    ConversionIssue::dropped("audio", NOT_SUPPORTED)
+                                        .at_message(i)
+                                        .at_part(j),
+                                )?,
                             }
                         }
                         messages.push(Message {
@@ -86,28 +156,201 @@ impl From<OpenaiRequestBody> for Request {
                             content: MessageContent::Blocks(blocks),
                         });
                     }
-                },
-                OpenaiMessage::Assistant(assistant) => {
-                    if let Some(text) = assistant.content {
+                }
+            }
+            OpenaiMessage::Assistant(assistant) => {
+                if assistant.name.is_some() {
+                    reporter
+                        .report(ConversionIssue::dropped("name", NOT_SUPPORTED).at_message(i))?;
+                }
+                match assistant.tool_calls {
+                    Some(tool_calls) if !tool_calls.is_empty() => {
+                        let mut blocks = Vec::with_capacity(tool_calls.len() + 1);
+                        if let Some(text) = assistant.content.filter(|t| !t.is_empty()) {
+                            blocks.push(ContentBlock::Base(BaseContentBlock::Text {
+                                text,
+                                cache_control: None,
+                            }));
+                        }
+                        for ToolCall::Function(call) in tool_calls {
+                            let input = match parse_tool_arguments(&call.function.arguments) {
+                                Some(input) => input,
+                                None => {
+                                    reporter.report(
+                                        ConversionIssue::degraded(
+                                            "arguments",
+                                            "invalid JSON, sent as an empty object",
+                                        )
+                                        .at_message(i),
+                                    )?;
+                                    serde_json::json!({})
+                                }
+                            };
+                            blocks.push(ContentBlock::Base(BaseContentBlock::ToolUse(
+                                ToolUseContentBlock {
+                                    id: call.id,
+                                    name: call.function.name,
+                                    input,
+                                },
+                            )));
+                        }
                         messages.push(Message {
                             role: Role::Assistant,
-                            content: MessageContent::Text(text),
+                            content: MessageContent::Blocks(blocks),
                         })
                     }
+                    _ => {
+                        if let Some(text) = assistant.content {
+                            messages.push(Message {
+                                role: Role::Assistant,
+                                content: MessageContent::Text(text),
+                            })
+                        }
+                    }
                 }
-                _ => {}
             }
-        }
-        res.system = system_message.map(System::Text);
-        res.messages = messages;
-        if let Some(stop) = body.stop {
-            match stop {
-                Stop::String(s) => res.stop_sequences = Some(vec![s]),
-                Stop::Array(ss) => res.stop_sequences = Some(ss),
+            OpenaiMessage::Tool(tool) => {
+                let block = ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult {
+                    tool_use_id: tool.tool_call_id,
+                    content: ToolResultContent::Text(tool.content),
+                    is_error: None,
+                    cache_control: None,
+                });
+                // Claude expects all results of one assistant turn in a single user message.
+                match messages.last_mut() {
+                    Some(Message {
+                        role: Role::User,
+                        content: MessageContent::Blocks(blocks),
+                    }) if is_tool_results(blocks) => blocks.push(block),
+                    _ => messages.push(Message {
+                        role: Role::User,
+                        content: MessageContent::Blocks(vec![block]),
+                    }),
+                }
             }
         }
-        res
     }
+    res.system = system_message.map(|(_, text)| System::Text(text));
+    res.messages = messages;
+    if let Some(stop) = body.stop {
+        match stop {
+            Stop::String(s) => res.stop_sequences = Some(vec![s]),
+            Stop::Array(ss) => res.stop_sequences = Some(ss),
+        }
+    }
+    res.tools = body
+        .tools
+        .map(|tools| tools.into_iter().map(Tool::from).collect());
+    let has_tools = res.tools.as_ref().is_some_and(|tools| !tools.is_empty());
+    res.tool_choice = tool_choice(body.tool_choice, body.parallel_tool_calls, has_tools);
+    Ok(reporter.finish(res))
+}
+
+const NOT_SUPPORTED: &str = "not supported by Claude";
+
+fn report_unsupported_fields(
+    body: &OpenaiRequestBody,
+    reporter: &mut Reporter,
+) -> Result<(), ConversionError> {
+    let unsupported = [
+        ("n", body.n.is_some_and(|n| n > 1)),
+        ("logit_bias", body.logit_bias.is_some()),
+        ("seed", body.seed.is_some()),
+        (
+            "response_format",
+            body.response_format
+                .as_ref()
+                .is_some_and(|f| *f != ResponseFormat::Text),
+        ),
+        ("frequency_penalty", body.frequency_penalty.is_some()),
+        ("presence_penalty", body.presence_penalty.is_some()),
+        ("logprobs", body.logprobs == Some(true)),
+        ("top_logprobs", body.top_logprobs.is_some()),
+        ("reasoning_effort", body.reasoning_effort.is_some()),
+        ("reasoning", body.reasoning.is_some()),
+        ("audio", body.audio.is_some()),
+        ("prediction", body.prediction.is_some()),
+        ("web_search_options", body.web_search_options.is_some()),
+        ("metadata", body.metadata.is_some()),
+        ("store", body.store == Some(true)),
+        (
+            "modalities",
+            body.modalities
+                .as_ref()
+                .is_some_and(|m| m.iter().any(|m| m != "text")),
+        ),
+        ("service_tier", body.service_tier.is_some()),
+    ];
+    for (field, present) in unsupported {
+        if present {
+            reporter.report(ConversionIssue::dropped(field, NOT_SUPPORTED))?;
+        }
+    }
+    Ok(())
+}
+
+impl From<OpenaiTool> for Tool {
+    fn from(tool: OpenaiTool) -> Self {
+        Tool {
+            name: tool.function.name,
+            description: tool.function.description,
+            // Claude requires an input schema, a function without parameters takes an empty object.
+            input_schema: tool.function.parameters.unwrap_or_else(|| {
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                })
+            }),
+            cache_control: None,
+        }
+    }
+}
+
+/// Openai spreads the tool choice over `tool_choice` and `parallel_tool_calls`,
+/// Claude puts both in `tool_choice`.
+fn tool_choice(
+    choice: Option<OpenaiToolChoice>,
+    parallel_tool_calls: Option<bool>,
+    has_tools: bool,
+) -> Option<ToolChoice> {
+    let disable_parallel_tool_use = parallel_tool_calls.map(|parallel| !parallel);
+    match choice {
+        Some(OpenaiToolChoice::None) => Some(ToolChoice::None),
+        Some(OpenaiToolChoice::Auto) => Some(ToolChoice::Auto {
+            disable_parallel_tool_use,
+        }),
+        Some(OpenaiToolChoice::Required) => Some(ToolChoice::Any {
+            disable_parallel_tool_use,
+        }),
+        Some(OpenaiToolChoice::Function(f)) => Some(ToolChoice::Tool {
+            name: f.function.name,
+            disable_parallel_tool_use,
+        }),
+        // `parallel_tool_calls` means nothing without tools
+        None if !has_tools => None,
+        None => disable_parallel_tool_use.map(|_| ToolChoice::Auto {
+            disable_parallel_tool_use,
+        }),
+    }
+}
+
+/// Openai sends the arguments as a JSON string, Claude expects an object.
+/// An empty string means a call without arguments.
+fn parse_tool_arguments(arguments: &str) -> Option<serde_json::Value> {
+    if arguments.trim().is_empty() {
+        return Some(serde_json::json!({}));
+    }
+    serde_json::from_str(arguments).ok()
+}
+
+fn is_tool_results(blocks: &[ContentBlock]) -> bool {
+    !blocks.is_empty()
+        && blocks.iter().all(|b| {
+            matches!(
+                b,
+                ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult { .. })
+            )
+        })
 }
 
 fn parse_mime_from_base64(s: &str) -> Option<String> {
@@ -124,6 +367,225 @@ fn parse_mime_from_base64(s: &str) -> Option<String> {
     }
 }
 
+/// Claude blocks dropped by `From<Request> for OpenaiRequestBody` are logged with this.
+fn warn_dropped(block: &ContentBlock) {
+    let kind = match block {
+        ContentBlock::Base(BaseContentBlock::Text { .. }) => "text",
+        ContentBlock::Base(BaseContentBlock::Thinking { .. }) => "thinking",
+        ContentBlock::Base(BaseContentBlock::ToolUse(_)) => "tool_use",
+        ContentBlock::RequestOnly(RequestOnlyContentBlock::Image { .. }) => "image",
+        ContentBlock::RequestOnly(RequestOnlyContentBlock::Document { .. }) => "document",
+        ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult { .. }) => "tool_result",
+        ContentBlock::RedactedThinking(_) => "redacted_thinking",
+    };
+    tracing::warn!("Openai request conversion: {} block dropped", kind);
+}
+
+fn image_content_part(source: ImageSource) -> ContentPart {
+    let url = match source {
+        ImageSource::Base64 { media_type, data } => to_data_url(&media_type, data),
+        ImageSource::Url { url } => url,
+    };
+    ContentPart::Image(ImageContentPart {
+        image_url: ImageUrl { url, detail: None },
+        dimensions: None,
+    })
+}
+
+/// Convert a Claude request to Openai's, so Anthropic-format requests can be fulfilled by Openai compatible backends.
+///
+/// What Openai can't carry is dropped and logged with `tracing::warn`:
+/// - thinking and redacted thinking blocks, Openai doesn't take reasoning back in a request
+/// - documents, user messages only take text and images
+/// - tool_use blocks in user messages and images or tool results in assistant messages
+/// - `is_error` of tool results and documents inside them
+///
+/// Tool results keep their text in the tool message, their images follow in the next user message.
+impl From<Request> for OpenaiRequestBody {
+    fn from(req: Request) -> Self {
+        let mut messages = Vec::with_capacity(req.messages.len() + 1);
+        if let Some(system) = req.system {
+            let content = match system {
+                System::Text(text) => text,
+                System::Blocks(blocks) => blocks
+                    .into_iter()
+                    .map(|b| b.text)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            };
+            messages.push(OpenaiMessage::System(OpenaiSystemMessage {
+                content,
+                name: None,
+            }));
+        }
+        for message in req.messages {
+            match (message.role, message.content) {
+                (Role::User, MessageContent::Text(text)) => {
+                    messages.push(OpenaiMessage::User(UserMessage {
+                        content: Content::Text(text),
+                        name: None,
+                    }))
+                }
+                (Role::User, MessageContent::Blocks(blocks)) => {
+                    let mut parts = vec![];
+                    for block in blocks {
+                        match block {
+                            ContentBlock::Base(BaseContentBlock::Text { text, .. }) => {
+                                parts.push(ContentPart::Text(TextContentPart { text }))
+                            }
+                            ContentBlock::RequestOnly(RequestOnlyContentBlock::Image {
+                                source,
+                                ..
+                            }) => parts.push(image_content_part(source)),
+                            // Openai carries tool results in their own messages,
+                            // they answer the previous assistant turn so they go first.
+                            ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult {
+                                tool_use_id,
+                                content,
+                                ..
+                            }) => {
+                                messages.push(OpenaiMessage::Tool(ToolMessage {
+                                    content: content.text(),
+                                    tool_call_id: tool_use_id,
+                                }));
+                                // a tool message only takes text
+                                if let ToolResultContent::Blocks(blocks) = content {
+                                    for block in blocks {
+                                        match block {
+                                            ContentBlock::RequestOnly(
+                                                RequestOnlyContentBlock::Image { source, .. },
+                                            ) => parts.push(image_content_part(source)),
+                                            ContentBlock::Base(BaseContentBlock::Text {
+                                                ..
+                                            }) => {}
+                                            block => warn_dropped(&block),
+                                        }
+                                    }
+                                }
+                            }
+                            block => warn_dropped(&block),
+                        }
+                    }
+                    if !parts.is_empty() {
+                        messages.push(OpenaiMessage::User(UserMessage {
+                            content: Content::Array(parts),
+                            name: None,
+                        }))
+                    }
+                }
+                (Role::Assistant, MessageContent::Text(text)) => {
+                    messages.push(OpenaiMessage::Assistant(AssistantMessage {
+                        content: Some(text),
+                        name: None,
+                        tool_calls: None,
+                    }))
+                }
+                (Role::Assistant, MessageContent::Blocks(blocks)) => {
+                    let mut content = String::new();
+                    let mut tool_calls = vec![];
+                    for block in blocks {
+                        match block {
+                            ContentBlock::Base(BaseContentBlock::Text { text, .. }) => {
+                                content.push_str(&text)
+                            }
+                            ContentBlock::Base(BaseContentBlock::ToolUse(tool_use)) => tool_calls
+                                .push(ToolCall::Function(ToolCallFunction {
+                                    id: tool_use.id,
+                                    function: ToolCallFunctionObj {
+                                        name: tool_use.name,
+                                        arguments: tool_use.input.to_string(),
+                                    },
+                                })),
+                            block => warn_dropped(&block),
+                        }
+                    }
+                    messages.push(OpenaiMessage::Assistant(AssistantMessage {
+                        content: (!content.is_empty()).then_some(content),
+                        name: None,
+                        tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                    }))
+                }
+            }
+        }
+
+        let (tool_choice, parallel_tool_calls) = match req.tool_choice {
+            Some(ToolChoice::Auto {
+                disable_parallel_tool_use,
+            }) => (
+                Some(OpenaiToolChoice::Auto),
+                disable_parallel_tool_use.map(|disable| !disable),
+            ),
+            Some(ToolChoice::Any {
+                disable_parallel_tool_use,
+            }) => (
+                Some(OpenaiToolChoice::Required),
+                disable_parallel_tool_use.map(|disable| !disable),
+            ),
+            Some(ToolChoice::Tool {
+                name,
+                disable_parallel_tool_use,
+            }) => (
+                Some(OpenaiToolChoice::Function(ToolChoiceFunction {
+                    r#type: ToolType::Function,
+                    function: FunctionName { name },
+                })),
+                disable_parallel_tool_use.map(|disable| !disable),
+            ),
+            Some(ToolChoice::None) => (Some(OpenaiToolChoice::None), None),
+            None => (None, None),
+        };
+
+        OpenaiRequestBody {
+            messages,
+            model: req.model,
+            max_completion_tokens: Some(req.max_tokens),
+            reasoning_effort: req
+                .thinking
+                .map(|Thinking::Enabled { budget_tokens }| reasoning_effort(budget_tokens)),
+            stop: req.stop_sequences.map(Stop::Array),
+            stream: req.stream,
+            temperature: req.temperature,
+            top_p: req.top_p,
+            tools: req
+                .tools
+                .map(|tools| tools.into_iter().map(openai_tool).collect()),
+            tool_choice,
+            parallel_tool_calls,
+            ..Default::default()
+        }
+    }
+}
+
+/// `From<Tool> for OpenaiTool` lives in the `tool` module, the request conversion can't depend on that feature.
+pub(crate) fn openai_tool(tool: Tool) -> OpenaiTool {
+    OpenaiTool {
+        r#type: ToolType::Function,
+        function: FunctionTool {
+            name: tool.name,
+            description: tool.description,
+            parameters: Some(tool.input_schema),
+        },
+    }
+}
+
+/// Map a thinking budget to the closest reasoning effort.
+/// Claude's minimum budget is 1024 tokens, anything below 4096 is considered low and 16384 and above is high.
+fn reasoning_effort(budget_tokens: u32) -> ReasoningEffort {
+    match budget_tokens {
+        0..4096 => ReasoningEffort::Low,
+        4096..16384 => ReasoningEffort::Medium,
+        _ => ReasoningEffort::High,
+    }
+}
+
+fn to_data_url(media_type: &str, data: String) -> String {
+    // the request conversion from Openai keeps the data url as is
+    if data.starts_with("data:") {
+        return data;
+    }
+    format!("data:{};base64,{}", media_type, data)
+}
+
 /// ClaudeEventDataParser can convert event data from Claude API to Openai API.
 /// It stores the intermidiate state of the parsing result and can be used to generate Openai's unary response.
 /// It provide two methods to parse the event data, `parse_str` and `parse_value`.
@@ -142,13 +604,13 @@ fn parse_mime_from_base64(s: &str) -> Option<String> {
 /// ````
 #[derive(Debug, Clone, PartialEq)]
 pub struct ClaudeEventDataParser {
-    usage: OpenaiUsage,
+    usage: Usage,
     parser: OpenaiEventDataParser,
     stop_reason: Option<StopReason>,
     stop_sequence: Option<String>,
     tool_call: Option<ToolCall>,
-    claude_tool_calls: Vec<ToolUseContentBlock>,
-    signature: Option<String>,
+    /// Claude's content blocks keyed by the event index, in the order of the server-side content array.
+    blocks: BTreeMap<u32, ResponseContentBlock>,
 }
 
 impl Default for ClaudeEventDataParser {
@@ -162,13 +624,12 @@ impl Default for ClaudeEventDataParser {
         let mut parser = OpenaiEventDataParser::default();
         parser.created = created_at;
         Self {
-            usage: OpenaiUsage::default(),
+            usage: Usage::default(),
             parser: OpenaiEventDataParser::default(),
             stop_reason: None,
             stop_sequence: None,
             tool_call: None,
-            claude_tool_calls: vec![],
-            signature: None,
+            blocks: BTreeMap::new(),
         }
     }
 }
@@ -184,44 +645,59 @@ impl EventDataParser<EventData> for ClaudeEventDataParser {
     ) -> Result<(Option<Chunk>, Option<ToolCall>), anyhow::Error> {
         match data {
             EventData::Error { error } => {
-                anyhow::bail!("Error from Claude API: {}", error);
+                // callers can downcast to `OpenaiErr`
+                Err(anyhow::Error::new(OpenaiErr::from(error.clone()))
+                    .context(format!("Error from Claude API: {}", error)))
             }
             EventData::MessageStart { message } => {
                 self.parser.update_id_if_empty(&message.id);
                 self.parser.update_model_if_empty(&message.model);
-                self.usage.prompt_tokens = message.usage.input_tokens.unwrap_or_default();
-                self.usage.completion_tokens = message.usage.output_tokens;
+                self.usage = message.usage.clone();
                 Ok((
                     Some(self.chunk_with_choice(0, None, None, Some(OpenaiRole::Assistant), None)),
                     None,
                 ))
             }
             EventData::ContentBlockStart {
-                index: _,
+                index,
                 content_block,
-            } => match content_block {
-                BaseContentBlock::ToolUse(tool_use) => {
-                    self.tool_call = Some(ToolCall::Function(ToolCallFunction {
-                        id: tool_use.id.to_string(),
-                        function: ToolCallFunctionObj {
-                            name: tool_use.name.to_string(),
-                            arguments: String::new(),
-                        },
-                    }));
-                    Ok((None, None))
+            } => {
+                let mut content_block = content_block.clone();
+                match &mut content_block {
+                    ResponseContentBlock::Base(BaseContentBlock::ToolUse(tool_use)) => {
+                        self.tool_call = Some(ToolCall::Function(ToolCallFunction {
+                            id: tool_use.id.to_string(),
+                            function: ToolCallFunctionObj {
+                                name: tool_use.name.to_string(),
+                                arguments: String::new(),
+                            },
+                        }));
+                    }
+                    // the signature arrives with a later signature_delta
+                    ResponseContentBlock::Base(BaseContentBlock::Thinking {
+                        signature, ..
+                    }) => {
+                        signature.take_if(|s| s.is_empty());
+                    }
+                    // redacted thinking is encrypted, there is nothing to stream
+                    _ => {}
                 }
-                BaseContentBlock::Text { text: _ } => Ok((None, None)),
-                BaseContentBlock::Thinking {
-                    thinking: _,
-                    signature: _,
-                } => Ok((None, None)),
-            },
+                self.blocks.insert(*index, content_block);
+                Ok((None, None))
+            }
             EventData::Ping => Ok((None, None)),
             EventData::ContentBlockDelta { index, delta } => match delta {
                 DeltaContentBlock::TextDelta { text } => {
+                    if let Some(ResponseContentBlock::Base(BaseContentBlock::Text {
+                        text: block,
+                        ..
+                    })) = self.blocks.get_mut(index)
+                    {
+                        block.push_str(text);
+                    }
                     self.parser.push_content(text);
                     Ok((
-                        Some(self.chunk_with_choice(*index as usize, Some(text), None, None, None)),
+                        Some(self.chunk_with_choice(0, Some(text), None, None, None)),
                         None,
                     ))
                 }
@@ -239,48 +715,72 @@ impl EventDataParser<EventData> for ClaudeEventDataParser {
                     Ok((None, None))
                 }
                 DeltaContentBlock::ThinkingDelta { thinking } => {
+                    if let Some(ResponseContentBlock::Base(BaseContentBlock::Thinking {
+                        thinking: block,
+                        ..
+                    })) = self.blocks.get_mut(index)
+                    {
+                        block.push_str(thinking);
+                    }
                     self.parser.push_thinking(thinking);
                     Ok((
-                        Some(self.chunk_with_choice(
-                            *index as usize,
-                            None,
-                            Some(thinking),
-                            None,
-                            None,
-                        )),
+                        Some(self.chunk_with_choice(0, None, Some(thinking), None, None)),
                         None,
                     ))
                 }
                 DeltaContentBlock::SignatureDelta { signature } => {
-                    self.signature = Some(signature.to_string());
+                    if let Some(ResponseContentBlock::Base(BaseContentBlock::Thinking {
+                        signature: block,
+                        ..
+                    })) = self.blocks.get_mut(index)
+                    {
+                        *block = Some(signature.to_string());
+                    }
                     Ok((None, None))
                 }
+                DeltaContentBlock::Unknown => Ok((None, None)),
             },
-            EventData::ContentBlockStop { index: _ } => {
+            EventData::ContentBlockStop { index } => {
                 if let Some(ToolCall::Function(function)) = self.tool_call.take() {
-                    let tool_call = ToolCall::Function(ToolCallFunction {
-                        id: function.id.to_string(),
-                        function: function.function.clone(),
-                    });
-                    self.parser.push_tool_call(tool_call.clone());
-                    if let Ok(obj) =
-                        serde_json::from_str::<serde_json::Value>(&function.function.arguments)
+                    // Claude streams the input as JSON, an empty one means no arguments
+                    let input = match function.function.arguments.as_str() {
+                        "" => Ok(serde_json::json!({})),
+                        arguments => serde_json::from_str::<serde_json::Value>(arguments),
+                    };
+                    // the block is kept even if its input is invalid, the next turn answers its id
+                    if let Some(ResponseContentBlock::Base(BaseContentBlock::ToolUse(tool_use))) =
+                        self.blocks.get_mut(index)
                     {
-                        self.claude_tool_calls.push(ToolUseContentBlock {
-                            id: function.id,
-                            name: function.function.name,
-                            input: obj,
-                        });
+                        tool_use.input = input
+                            .as_ref()
+                            .map_or_else(|_| serde_json::json!({}), Clone::clone);
                     }
+                    // e.g. cut off by max_tokens, the call still goes out with the raw arguments
+                    if let Err(e) = input {
+                        tracing::warn!("invalid input of tool_use block {}: {}", index, e);
+                    }
+                    let tool_call = ToolCall::Function(function);
+                    self.parser.push_tool_call(tool_call.clone());
                     return Ok((None, Some(tool_call)));
                 }
                 Ok((None, None))
             }
             EventData::MessageDelta { delta, usage } => {
-                self.usage.completion_tokens += usage.output_tokens;
+                self.usage.output_tokens += usage.output_tokens;
+                // the delta may repeat the input and cache counts
+                if usage.input_tokens.is_some() {
+                    self.usage.input_tokens = usage.input_tokens;
+                }
+                if usage.cache_creation_input_tokens.is_some() {
+                    self.usage.cache_creation_input_tokens = usage.cache_creation_input_tokens;
+                }
+                if usage.cache_read_input_tokens.is_some() {
+                    self.usage.cache_read_input_tokens = usage.cache_read_input_tokens;
+                }
                 self.parser
                     .set_finish_reason(Some(delta.stop_reason.clone().into()));
                 self.stop_reason = Some(delta.stop_reason.clone());
+                self.stop_sequence = delta.stop_sequence.clone();
                 Ok((
                     Some(self.chunk_with_choice(
                         0,
@@ -293,59 +793,31 @@ impl EventDataParser<EventData> for ClaudeEventDataParser {
                 ))
             }
             EventData::MessageStop => Ok((Some(Chunk::Done), None)),
+            EventData::Unknown => Ok((None, None)),
         }
     }
 
     fn response(mut self) -> OpenaiResponse {
         self.parser.object = "chat.completion".to_string();
         let mut res = self.parser.response();
-        res.usage = OpenaiUsage {
-            prompt_tokens: self.usage.prompt_tokens,
-            completion_tokens: self.usage.completion_tokens,
-            total_tokens: self.usage.prompt_tokens + self.usage.completion_tokens,
-            completion_tokens_details: None,
-            prompt_tokens_details: None,
-        };
+        res.usage = self.usage.into();
         res
     }
 }
 
 impl ClaudeEventDataParser {
+    /// The streamed message in Claude's format, the content blocks are in the order they were streamed.
+    /// Thinking signatures and redacted thinking are kept, so it can be sent back verbatim in the next turn.
     pub fn claude_response(&self) -> async_claude::messages::Response {
-        let mut content = vec![];
-        if !self.parser.think_content.is_empty() {
-            content.push(ResponseContentBlock::Base(BaseContentBlock::Thinking {
-                thinking: self.parser.think_content.clone(),
-                signature: self.signature.clone(),
-            }));
-        }
-        if !self.parser.content.is_empty() {
-            content.push(ResponseContentBlock::Base(BaseContentBlock::Text {
-                text: self.parser.content.clone(),
-            }));
-        }
-        for tool_call in self.claude_tool_calls.iter() {
-            content.push(ResponseContentBlock::Base(BaseContentBlock::ToolUse(
-                ToolUseContentBlock {
-                    id: tool_call.id.to_string(),
-                    name: tool_call.name.to_string(),
-                    input: tool_call.input.clone(),
-                },
-            )));
-        }
-
         async_claude::messages::Response {
             id: self.parser.id.to_string(),
             r#type: "message".to_string(),
             role: Role::Assistant,
-            content,
+            content: self.blocks.values().cloned().collect(),
             model: self.parser.model.to_string(),
             stop_reason: self.stop_reason.clone(),
-            stop_sequence: None,
-            usage: Usage {
-                input_tokens: Some(self.usage.prompt_tokens),
-                output_tokens: self.usage.completion_tokens,
-            },
+            stop_sequence: self.stop_sequence.clone(),
+            usage: self.usage.clone(),
         }
     }
     pub fn default_chunk(&self) -> Chunk {
@@ -403,6 +875,76 @@ impl ClaudeEventDataParser {
         let payload = serde_json::from_value::<EventData>(d)?;
         self.parse(&payload)
     }
+
+    /// The tool use block still being streamed, with the `partial_json` received so far.
+    pub fn partial_tool_call(&self) -> Option<PartialToolCall> {
+        match self.tool_call.as_ref()? {
+            ToolCall::Function(function) => Some(PartialToolCall::new(
+                function.id.as_str(),
+                function.function.name.as_str(),
+                &function.function.arguments,
+            )),
+        }
+    }
+}
+
+impl FromSseEvent for EventData {
+    type Error = serde_json::Error;
+
+    fn from_sse_event(event: &SseEvent) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(&event.data)
+    }
+}
+
+impl FromSseEvent for (EventName, EventData) {
+    type Error = serde_json::Error;
+
+    fn from_sse_event(event: &SseEvent) -> Result<Self, serde_json::Error> {
+        let Ok(name) = event.event.as_deref().unwrap_or_default().parse();
+        Ok((name, EventData::from_sse_event(event)?))
+    }
+}
+
+/// Framed with the `event` line, e.g. `event: message_stop`.
+impl ToSseEvent for EventData {
+    fn to_sse_event(&self) -> Result<SseEvent, serde_json::Error> {
+        Ok(SseEvent {
+            event: Some(self.event_name().as_str().to_string()),
+            data: serde_json::to_string(self)?,
+            ..Default::default()
+        })
+    }
+}
+
+/// `code` is the HTTP status Claude uses for the error type, empty for unknown types.
+impl From<ErrorData> for OpenaiErr {
+    fn from(error: ErrorData) -> Self {
+        let (r#type, code, message) = match error {
+            ErrorData::OverloadedError { message } => ("overloaded_error", "529", message),
+            ErrorData::InternalServerError { message } => ("internal_server_error", "500", message),
+            ErrorData::BadRequestError { message } => ("bad_request_error", "400", message),
+            ErrorData::UnauthorizedError { message } => ("unauthorized_error", "401", message),
+            ErrorData::AuthenticationError { message } => ("authentication_error", "401", message),
+            ErrorData::PermissionError { message } => ("permission_error", "403", message),
+            ErrorData::NotFoundError { message } => ("not_found_error", "404", message),
+            ErrorData::RequestTooLarge { message } => ("request_too_large", "413", message),
+            ErrorData::RateLimitError { message } => ("rate_limit_error", "429", message),
+            ErrorData::ApiError { message } => ("api_error", "500", message),
+            ErrorData::Other { r#type, message } => {
+                return OpenaiErr {
+                    message,
+                    r#type,
+                    ..Default::default()
+                };
+            }
+        };
+        OpenaiErr {
+            message,
+            r#type: r#type.to_string(),
+            param: String::new(),
+            code: code.to_string(),
+        }
+    }
 }
 
 impl From<StopReason> for FinishReason {
@@ -412,15 +954,132 @@ impl From<StopReason> for FinishReason {
             StopReason::MaxTokens => FinishReason::Length,
             StopReason::StopSequence => FinishReason::Stop,
             StopReason::ToolUse => FinishReason::ToolCalls,
+            StopReason::Refusal => FinishReason::ContentFilter,
+            StopReason::PauseTurn | StopReason::Unknown => FinishReason::Stop,
+        }
+    }
+}
+
+impl From<Usage> for OpenaiUsage {
+    fn from(usage: Usage) -> Self {
+        // Claude's input tokens don't include the cached ones, Openai's prompt tokens do.
+        let cache_read = usage.cache_read_input_tokens.unwrap_or_default();
+        let prompt_tokens = usage.input_tokens.unwrap_or_default()
+            + usage.cache_creation_input_tokens.unwrap_or_default()
+            + cache_read;
+        OpenaiUsage {
+            completion_tokens: usage.output_tokens,
+            prompt_tokens,
+            total_tokens: prompt_tokens + usage.output_tokens,
+            completion_tokens_details: None,
+            prompt_tokens_details: usage.cache_read_input_tokens.map(|_| PromptTokensDetails {
+                cached_tokens: Some(cache_read),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+impl From<OpenaiUsage> for Usage {
+    fn from(usage: OpenaiUsage) -> Self {
+        let cache_read = usage
+            .prompt_tokens_details
+            .and_then(|details| details.cached_tokens);
+        Usage {
+            input_tokens: Some(
+                usage
+                    .prompt_tokens
+                    .saturating_sub(cache_read.unwrap_or_default()),
+            ),
+            output_tokens: usage.completion_tokens,
+            cache_creation_input_tokens: None,
+            cache_read_input_tokens: cache_read,
+        }
+    }
+}
+
+impl From<FinishReason> for StopReason {
+    fn from(reason: FinishReason) -> Self {
+        match reason {
+            FinishReason::Stop => StopReason::EndTurn,
+            FinishReason::Length => StopReason::MaxTokens,
+            FinishReason::ToolCalls => StopReason::ToolUse,
+            FinishReason::ContentFilter => StopReason::Refusal,
+        }
+    }
+}
+
+/// Convert a non-streaming Claude response to Openai's chat completion.
+impl From<Response> for OpenaiResponse {
+    fn from(res: Response) -> Self {
+        let mut content = String::new();
+        let mut reasoning = String::new();
+        let mut tool_calls = vec![];
+        for block in res.content {
+            match block {
+                ResponseContentBlock::Base(BaseContentBlock::Text { text, .. }) => {
+                    content.push_str(&text)
+                }
+                ResponseContentBlock::Base(BaseContentBlock::Thinking { thinking, .. }) => {
+                    reasoning.push_str(&thinking)
+                }
+                ResponseContentBlock::Base(BaseContentBlock::ToolUse(tool_use)) => {
+                    tool_calls.push(ToolCall::Function(ToolCallFunction {
+                        id: tool_use.id,
+                        function: ToolCallFunctionObj {
+                            name: tool_use.name,
+                            arguments: tool_use.input.to_string(),
+                        },
+                    }))
+                }
+                ResponseContentBlock::RedactedThinking(_) => {}
+                // Claude ran the tool itself, Openai has no message for the result
+                ResponseContentBlock::ServerToolResult(_) => {}
+            }
+        }
+        OpenaiResponse {
+            id: res.id,
+            choices: vec![
+                OpenaiResponseChoice {
+                    index: 0,
+                    message: OpenaiResponseMessage {
+                        content: (!content.is_empty()).then_some(content),
+                        reasoning: (!reasoning.is_empty()).then_some(reasoning),
+                        tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                        role: OpenaiRole::Assistant,
+                        ..Default::default()
+                    },
+                    finish_reason: res.stop_reason.map(FinishReason::from),
+                    logprobs: None,
+                },
+            ],
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            model: res.model,
+            service_tier: None,
+            system_fingerprint: None,
+            object: "chat.completion".to_string(),
+            usage: res.usage.into(),
         }
     }
 }
 
 #[cfg(feature = "claude-price")]
 pub fn price(model: &str, usage: &OpenaiUsage) -> f32 {
+    // prompt_tokens include the cache reads, bill those at the cache rate
+    let cached_tokens = usage
+        .prompt_tokens_details
+        .as_ref()
+        .and_then(|details| details.cached_tokens)
+        .unwrap_or_default()
+        .min(usage.prompt_tokens);
     let claude_usage = Usage {
-        input_tokens: Some(usage.prompt_tokens),
+        input_tokens: Some(usage.prompt_tokens - cached_tokens),
         output_tokens: usage.completion_tokens,
+        cache_read_input_tokens: Some(cached_tokens),
+        ..Default::default()
     };
     async_claude::price(model, &claude_usage)
 }
@@ -428,26 +1087,31 @@ pub fn price(model: &str, usage: &OpenaiUsage) -> f32 {
 #[cfg(test)]
 mod tests {
     use crate::{
+        convert::{ConversionError, ConversionIssue, ConversionMode},
         entity::{
             chat_completion_chunk::{Choice, Chunk, ChunkResponse, DeltaMessage},
             chat_completion_object::{
-                Choice as OpenaiResponseChoice, Message as OpenaiMessage,
+                Choice as OpenaiResponseChoice, Message as OpenaiMessage, PromptTokensDetails,
                 Response as OpenaiResponse, Role as OpenaiRole, Usage,
             },
             create_chat_completion::{
-                FinishReason, RequestBody, ToolCall, ToolCallFunction, ToolCallFunctionObj,
+                AssistantMessage, Content, ContentPart, FinishReason, FunctionTool,
+                ImageContentPart, ImageUrl, Message as RequestMessage, ReasoningEffort,
+                RequestBody, Stop, SystemMessage, TextContentPart, Tool as OpenaiTool, ToolCall,
+                ToolCallFunction, ToolCallFunctionObj, ToolChoice as OpenaiToolChoice, ToolMessage,
+                ToolType, UserMessage,
             },
         },
         magi::EventDataParser,
     };
 
-    use anyhow::anyhow;
     use async_claude::messages::{
         BaseContentBlock, ContentBlock, ImageSource, Message, MessageContent,
-        RequestOnlyContentBlock, Role, StopReason, System, request::Request,
+        RedactedThinkingContentBlock, RequestOnlyContentBlock, Role, StopReason, System, Tool,
+        ToolChoice, ToolUseContentBlock, request::Request,
     };
 
-    use super::ClaudeEventDataParser;
+    use super::{ClaudeEventDataParser, EventData, EventName, OpenaiErr, convert_with_report};
 
     #[test]
     fn convert_request() {
@@ -476,12 +1140,14 @@ mod tests {
                         content: MessageContent::Blocks(vec![
                             ContentBlock::Base(BaseContentBlock::Text {
                                 text: "What's in this image?".to_string(),
+                                cache_control: None,
                             }),
                             ContentBlock::RequestOnly(RequestOnlyContentBlock::Image {
                                 source: ImageSource::Base64 {
                                     media_type: "image/png".to_string(),
                                     data: "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAALgAAAAmCAYAAAB3X1H0AAABnGlUWHRYTUw6Y29tLmFkb2JlLnhtcAAAAAAAPD94cGFja2V0IGJlZ2luPSLvu78iIGlkPSJXNU0wTXBDZWhpSHpyZVN6TlRjemtjOWQiPz4KPHg6eG1wbWV0YSB4bWxuczp4PSJhZG9iZTpuczptZXRhLyIgeDp4bXB0az0iWE1QIENvcmUgNi4wLjAiPgogPHJkZjpSREYgeG1sbnM6cmRmPSJodHRwOi8vd3d3LnczLm9yZy8xOTk5LzAyLzIyLXJkZi1zeW50YXgtbnMjIj4KICA8cmRmOkRlc2NyaXB0aW9uIHJkZjphYm91dD0iIgogICAgeG1sbnM6ZXhpZj0iaHR0cDovL25zLmFkb2JlLmNvbS9leGlmLzEuMC8iCiAgIGV4aWY6Q29sb3JTcGFjZT0iMSIKICAgZXhpZjpQaXhlbFhEaW1lbnNpb249IjE4NCIKICAgZXhpZjpQaXhlbFlEaW1lbnNpb249IjM4Ii8+CiA8L3JkZjpSREY+CjwveDp4bXBtZXRhPgo8P3hwYWNrZXQgZW5kPSJyIj8+WCK4LwAAAAFzUkdCAK7OHOkAAAt9SURBVHgB7ZxnqFVHEIDXHhU0auzGFnvsvYEdQRIbWMAuqKAgNuxd0SAo4g97FyUEDSgqlkTFH3YFW0zsvfeuWOK3OMc5553br+bleQbu293Z2TY7OzszezTdzp0735sAAg6kUQ5kZF2NGzdOo8sLlvU1c2DXrl0m/dfMgGDtaZ8DgYCn/T3+qlcYCPhXvf1pf/GBgKf9Pf6qVxgI+Fe9/Wl/8TaKEs0yjx8/bi5evOgizZ49u8mbN68pVqyYyZEjh6suKETmwP37983u3bvN+fPnLXGRIkVMnTp1LD8jtw4oouFA1AK+ceNGs27dupB99ujRw/Tv399kyJAhJE1Q8YkDBw4cMMOGDTPPnz//hPyYa9eunRkzZkwKfFpEvHv3zty8edMuLX/+/EmXn6SZKCtWrDCrVq1Ki3uQ9DU9fvw4pHAzWO7cuZM+Zmrt8NixY6Z169b257UQkjHnuAS8cOHCpn379qZo0aKuOaxZs8ZVDgr+HNi2bZtLc1epUsX06dPHVKtWzTZo06aNf8M0iN27d+9nXVXUJoqeRY0aNczo0aMtipQNA7Apnz17ZrDNOY2XL1+2+IoVK5pvvvnG8LJ048YN07x5c8fOfPv2rTl58qT5+++/zaNHj0z58uVN5cqVXTb9oUOHHIHgUBUvXtz2S//61GfLls3UrFnT1r18+dJgBgiApx44evSo+eeff8ydO3esD1GuXDk7ptCSvn//3pw6dcrO7eHDh6Z06dKmevXqrnk9ePDA4JsA2M/Mi7nSP0Jbu3ZtW+f9453zwoUL7dXcr18/c+nSJVOoUCHbBF6dOXPG5jNmzGjq16/vdMXaWCNQokQJ8/333xs9n1KlSpmCBQva+R08eNDky5fPNGjQwHU7xEovg0ezZ9CyznAygP+2fft26dbu17Vr16zilD12KuPMxCXgeiw2XQQcfLp06Ww1Nvvy5cttfuzYsWbr1q0GRgNlypSxAs5Vja3pPcVc0bNnzzYVKlSw9PPmzbNCQwHtNm7cOIvnxli7dq3Nyx/GYA4IxpAhQwRttmzZYtKnT2+GDx9u9uzZ4+Al07BhQzsm5Tdv3pjp06eb9evXS7VNEZJZs2YZDgTAAZAxWrVqZdfEXIHu3buHFHDWLZArVy6X3YnDLsA8mYcAh0cAnqJQAHyf3r17u+YDn7hp586dK02scGNKIviAnn809LSJds+gDScDKMGVK1ea27dvQ2ph5syZNu3Vq5cZMGDAR2xiSVwmih7y9OnTThGtJVrSQX7IwGQRbvA4E8DgwYNTCDd4Ng4B4TQDP/74o035IxqNPFrYC+KwXLhwwaniwHz33XdmyZIlvsINIQdVYM6cOSmEmzo2o2/fvo7mFHpSbicRbsoFChQg8QW0rQBrPHLkiBSTlnI4tXDTMXydOHGi7xjR0ke7Z95BvDLAurVwe+mTVY5LwE+cOGEWL15suFJ///13Zy5NmzZ18jojmgbhx8Rg8xF4rnIB2tKfhtWrV9uiaEwKf/31l8Hz5ifmgW4jgn3u3DkHzcED/vzzTweHyYEgoymwfdu2bWvr0FDal/j555+t1peGRD02bNggRSfV0ZCSJUtak8Wp9GT0gaWKQ7Njxw4PVeJF+D1w4EDnJqTHw4cPWzPRr/dI9LHsmbd/rww0a9bM9OzZ00UGH8aPH29CyZGLOMpCXAJO3Hb+/PmWWTLO5MmTTZcuXaSYIkVQPnyaaw8EMXN9/XOVchXjaLFIgd9++81qS0waDbdu3XK0O3hMB4GzZ8/arL5ZsOu9gCmATct1uGjRIse2/uOPP1ykQ4cONR07drR+g1To20hwpBwaDhHz1vaypiFfr149x6GUOkwn5sHBTRYMGjTI3oRerb1//37fISLRx7JnfgNoGahatapp0qSJiwyhJ6Lit18uwhgKcQm4X//YzIR8QgGaUsfItaOF/St1devWdXWByeF1OHDE5HEEYv25rwg4jquA3ACMI4AgY0+/fv1aUDYVs4gCGo2DggmBkydw9epVybpSDmjOnDldOL8CPsIvv/zi0qzQLViwwOL92sSDw1kHuFFYiwDOtR9Eoo9lz/z698qAH02ycXEJOM4fVwmhQgGuIB4u/EBsYF0n3jU4rYGxlTVgpyFclSpVctCYISLIILW2JBpz7949J+pCvdwA+iCAxxTp3Lmz0eYMkQsBzA5uFH44qQL6EAiOFA0eLeTJk8cKdKNGjVxNMPn27dvnwsVb0IeNW1JAzAUpSxqJPpY9kz4l9ZMBqfucaVwCTgiKq4QQ4YQJE5z5wTjNBKlgMzVwDWubleiGQKZMmSRr0xcvXthUCzjaWzubCLAIF3Xa+dSMxZGU8KYMwnw7derkzJtQZbygBSSaPrJmzWpmzJhhD5mmj0bAJUSo23nzciuC13wVnsZCH8+e6f69MqDrPmf+070b5yhyrUlzHFDvA5DUSYpAc22KmfHq1Supcgk+SMJogJgZ5Gn35MkTsvbqJZ6KgIvQawERB9MSf/jDrcMNNGLECJcdTwQEP0DfJsxx5MiR0tRJtbA4yDgzCCE3HyFBUQ6hbGTiz9B7hS3U0CgRDjjw9OlTh0xwDuJjJhx9PHvm7f+/KH9SnXGOfuXKlbhaaoHVJoJEQaRThBcQDU2eMeVw4JBg03KrCGgB93NYGBvzRM9BIjryyEJfCAXOEJpf//RtImPGkvo5knxkJYCJBWgNTFnMp2h5Lr4CCkQOD/2I0iCvIRK95lc0e6b7/q/ycQk4jhmbz8PDsmXLXHPnRS8a0ALJaxaRETZehx3RNKJRea0T0DZk2bJlLRptKyDCT1lvCqE4uS14aJBXT+h43AGkP/LY/5s2bSLrAKFJnNxEAGeUiIkIMuPoxyd57PFe64T4AO+cQs1l8+bNtooYvQbNS42PRB/rnum+/fJZsmRxoeUAu5AJFuIyUXC4tNMlc0AYtYAI3i9t2bKljaWLLd6hQwf7GKM1DXFS0WLiaHpj3z/88IPtPtSmac1PuIxPBoimYGboryNl3tRp82nSpEk2vPntt9/alz/MoGnTpjmfGvitLRIO84qICT+iG8IDaSffpGjHkLopU6YY3gb0AZY2fikCy82knWLGC/UJQST6WPfMb04aJ8pLcPhHrJkb0usrCU2saVwaPNQgRFa8pzIULa+ZhMoE2GQt3AimPL4IjfeBBLxobnl+FlpSbgAxcQSP9uehRgs3dd26dbMk2JreaBDfbNNGbHzpKxmpV7gRwK5du9quWZvXhxDh1gc33Dy0cEPHZ82ZM2cO2SQcfTx7FnKgDxW8h9SqVcshgRfwOBoH2mkUIRO1gOtIh+6TE8fHUzxu6Bi2phctrNuRJ7zHdyU6Rgv+p59+MkuXLk2B97OnJUbOePLtCn0AXuFAcLw0aBEiGVqrkf/1119T0NJnixYtHLxeI3XyHQ75cEBo0s8Rhx+Mq//xyNSpU11mFoeWyBUPVALeeQheKxBwPFjxzUooiIY+lj3T8wolA6NGjXKUlMxLTDQpJ5Km4z/+ady4cSJ9JNwW+xf7i5SND8WMhAf62AGaAkcN7Y7DFU4w0SbXr1+3NxP04bRfrPPjs4C7d+/aZiiKcLcfjzP4PWy+Fhw9JnY8T/MCfARH6BLnkYNMWFJDrPS6bTL3jC838UP4EpX1JWv/8T3issH1QpORx77WHyAlo89wfXBjiM0djo46bHYxgyLRxlqPptbaOlx7DpfX3ApHL3UISywaMVr6ZO4ZCkY+wJN5JyuN2kRJ1oBBPwEHviQHAgH/ktwOxvriHAgE/IuzPBjwS3IgVdjgX3LBaXksYvU67Bbpk4JY6f+PvEsVUZT/I+OCOad+DhBFCUyU1L9PwQwT4EAg4AkwL2ia+jkQCHjq36NghglwIBDwBJgXNE39HAgEPPXvUTDDBDhgw4Te74UT6C9oGnAgVXHgX+rCSB0jTfe/AAAAAElFTkSuQmCC".to_string(),
                                 },
+                                cache_control: None,
                             }),
                         ]),
                     }],
@@ -489,6 +1155,159 @@ mod tests {
                     ..Default::default()
                 },
             ),
+            (
+                "tools",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"What's the weather like in Boston today?"}],"tools":[{"type":"function","function":{"name":"get_current_weather","description":"Get the current weather in a given location","parameters":{"type":"object","properties":{"location":{"type":"string"}},"required":["location"]}}},{"type":"function","function":{"name":"get_time"}}],"tool_choice":{"type":"function","function":{"name":"get_current_weather"}},"parallel_tool_calls":false}"#,
+                Request {
+                    model: "gpt-4o".to_string(),
+                    messages: vec![Message {
+                        role: Role::User,
+                        content: MessageContent::Text(
+                            "What's the weather like in Boston today?".to_string(),
+                        ),
+                    }],
+                    max_tokens: 4000,
+                    tools: Some(vec![
+                        Tool {
+                            name: "get_current_weather".into(),
+                            description: Some("Get the current weather in a given location".into()),
+                            input_schema: serde_json::json!({
+                                "type": "object",
+                                "properties": {"location": {"type": "string"}},
+                                "required": ["location"]
+                            }),
+                            cache_control: None,
+                        },
+                        Tool {
+                            name: "get_time".into(),
+                            description: None,
+                            input_schema: serde_json::json!({"type": "object", "properties": {}}),
+                            cache_control: None,
+                        },
+                    ]),
+                    tool_choice: Some(ToolChoice::Tool {
+                        name: "get_current_weather".to_string(),
+                        disable_parallel_tool_use: Some(true),
+                    }),
+                    ..Default::default()
+                },
+            ),
+            (
+                "tool call history",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"What's the weather like in Boston and Tokyo?"},{"role":"assistant","content":null,"tool_calls":[{"id":"call_1","type":"function","function":{"name":"get_current_weather","arguments":"{\"location\":\"Boston\"}"}},{"id":"call_2","type":"function","function":{"name":"get_current_weather","arguments":"{\"location\":\"Tokyo\"}"}}]},{"role":"tool","tool_call_id":"call_1","content":"12 degrees"},{"role":"tool","tool_call_id":"call_2","content":"20 degrees"},{"role":"assistant","content":"Boston is 12 degrees and Tokyo is 20 degrees."}]}"#,
+                Request {
+                    model: "gpt-4o".to_string(),
+                    messages: vec![
+                        Message {
+                            role: Role::User,
+                            content: MessageContent::Text(
+                                "What's the weather like in Boston and Tokyo?".to_string(),
+                            ),
+                        },
+                        Message {
+                            role: Role::Assistant,
+                            content: MessageContent::Blocks(vec![
+                                ContentBlock::Base(BaseContentBlock::ToolUse(ToolUseContentBlock {
+                                    id: "call_1".to_string(),
+                                    name: "get_current_weather".to_string(),
+                                    input: serde_json::json!({"location": "Boston"}),
+                                })),
+                                ContentBlock::Base(BaseContentBlock::ToolUse(ToolUseContentBlock {
+                                    id: "call_2".to_string(),
+                                    name: "get_current_weather".to_string(),
+                                    input: serde_json::json!({"location": "Tokyo"}),
+                                })),
+                            ]),
+                        },
+                        Message {
+                            role: Role::User,
+                            content: MessageContent::Blocks(vec![
+                                ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult {
+                                    tool_use_id: "call_1".to_string(),
+                                    content: "12 degrees".into(),
+                                    is_error: None,
+                                    cache_control: None,
+                                }),
+                                ContentBlock::RequestOnly(RequestOnlyContentBlock::ToolResult {
+                                    tool_use_id: "call_2".to_string(),
+                                    content: "20 degrees".into(),
+                                    is_error: None,
+                                    cache_control: None,
+                                }),
+                            ]),
+                        },
+                        Message {
+                            role: Role::Assistant,
+                            content: MessageContent::Text(
+                                "Boston is 12 degrees and Tokyo is 20 degrees.".to_string(),
+                            ),
+                        },
+                    ],
+                    max_tokens: 4000,
+                    ..Default::default()
+                },
+            ),
+            (
+                "tool choice none",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hello!"}],"tool_choice":"none"}"#,
+                Request {
+                    model: "gpt-4o".to_string(),
+                    messages: vec![Message {
+                        role: Role::User,
+                        content: MessageContent::Text("Hello!".to_string()),
+                    }],
+                    max_tokens: 4000,
+                    tool_choice: Some(ToolChoice::None),
+                    ..Default::default()
+                },
+            ),
+            (
+                "image url",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":[{"type":"image_url","image_url":{"url":"https://example.com/cat.png"}}]}]}"#,
+                Request {
+                    model: "gpt-4o".to_string(),
+                    messages: vec![Message {
+                        role: Role::User,
+                        content: MessageContent::Blocks(vec![ContentBlock::RequestOnly(
+                            RequestOnlyContentBlock::Image {
+                                source: ImageSource::Url {
+                                    url: "https://example.com/cat.png".to_string(),
+                                },
+                                cache_control: None,
+                            },
+                        )]),
+                    }],
+                    max_tokens: 4000,
+                    ..Default::default()
+                },
+            ),
+            (
+                "user",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hello!"}],"user":"user-1"}"#,
+                Request {
+                    model: "gpt-4o".to_string(),
+                    messages: vec![Message {
+                        role: Role::User,
+                        content: MessageContent::Text("Hello!".to_string()),
+                    }],
+                    max_tokens: 4000,
+                    metadata: Some(serde_json::json!({"user_id": "user-1"})),
+                    ..Default::default()
+                },
+            ),
+            (
+                "parallel tool calls without tools",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hello!"}],"parallel_tool_calls":false}"#,
+                Request {
+                    model: "gpt-4o".to_string(),
+                    messages: vec![Message {
+                        role: Role::User,
+                        content: MessageContent::Text("Hello!".to_string()),
+                    }],
+                    max_tokens: 4000,
+                    ..Default::default()
+                },
+            ),
         ];
         for (name, json, want) in tests {
             //test deserialize
@@ -498,6 +1317,428 @@ mod tests {
         }
     }
 
+    #[test]
+    fn convert_claude_request() {
+        let tests = vec![
+            (
+                "simple",
+                r#"{"model":"claude-3-opus-20240229","max_tokens":1024,"system":"You are a helpful assistant.","messages":[{"role":"user","content":"Hello, world"}]}"#,
+                RequestBody {
+                    model: "claude-3-opus-20240229".to_string(),
+                    max_completion_tokens: Some(1024),
+                    messages: vec![
+                        RequestMessage::System(SystemMessage {
+                            content: "You are a helpful assistant.".to_string(),
+                            name: None,
+                        }),
+                        RequestMessage::User(UserMessage {
+                            content: Content::Text("Hello, world".to_string()),
+                            name: None,
+                        }),
+                    ],
+                    ..Default::default()
+                },
+            ),
+            (
+                "blocks, tools and thinking",
+                r#"{
+                "model": "claude-3-7-sonnet-20250219",
+                "max_tokens": 2048,
+                "system": [{"type": "text", "text": "You are a weather bot."}, {"type": "text", "text": "Be brief."}],
+                "stop_sequences": ["END"],
+                "thinking": {"type": "enabled", "budget_tokens": 8000},
+                "tools": [{"name": "get_weather", "description": "Get the weather", "input_schema": {"type": "object", "properties": {"location": {"type": "string"}}}}],
+                "tool_choice": {"type": "auto", "disable_parallel_tool_use": true},
+                "messages": [
+                    {"role": "user", "content": [
+                        {"type": "text", "text": "What is the weather here?"},
+                        {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo"}}
+                    ]},
+                    {"role": "assistant", "content": [
+                        {"type": "thinking", "thinking": "I should call the tool.", "signature": "EqQBCgIYAhIM"},
+                        {"type": "text", "text": "Let me check."},
+                        {"type": "tool_use", "id": "toolu_01", "name": "get_weather", "input": {"location": "Paris"}}
+                    ]},
+                    {"role": "user", "content": [
+                        {"type": "tool_result", "tool_use_id": "toolu_01", "content": "15 degrees"},
+                        {"type": "text", "text": "Thanks"}
+                    ]}
+                ]
+            }"#,
+                RequestBody {
+                    model: "claude-3-7-sonnet-20250219".to_string(),
+                    max_completion_tokens: Some(2048),
+                    reasoning_effort: Some(ReasoningEffort::Medium),
+                    stop: Some(Stop::Array(vec!["END".to_string()])),
+                    tools: Some(vec![
+                        OpenaiTool {
+                            r#type: ToolType::Function,
+                            function: FunctionTool {
+                                name: "get_weather".into(),
+                                description: Some("Get the weather".into()),
+                                parameters: Some(serde_json::json!({
+                                    "type": "object",
+                                    "properties": {"location": {"type": "string"}}
+                                })),
+                            },
+                        },
+                    ]),
+                    tool_choice: Some(OpenaiToolChoice::Auto),
+                    parallel_tool_calls: Some(false),
+                    messages: vec![
+                        RequestMessage::System(SystemMessage {
+                            content: "You are a weather bot.\nBe brief.".to_string(),
+                            name: None,
+                        }),
+                        RequestMessage::User(UserMessage {
+                            content: Content::Array(vec![
+                                ContentPart::Text(TextContentPart {
+                                    text: "What is the weather here?".to_string(),
+                                }),
+                                ContentPart::Image(ImageContentPart {
+                                    image_url: ImageUrl {
+                                        url: "data:image/png;base64,iVBORw0KGgo".to_string(),
+                                        detail: None,
+                                    },
+                                    dimensions: None,
+                                }),
+                            ]),
+                            name: None,
+                        }),
+                        RequestMessage::Assistant(AssistantMessage {
+                            content: Some("Let me check.".to_string()),
+                            name: None,
+                            tool_calls: Some(vec![
+                                ToolCall::Function(ToolCallFunction {
+                                    id: "toolu_01".to_string(),
+                                    function: ToolCallFunctionObj {
+                                        name: "get_weather".to_string(),
+                                        arguments: r#"{"location":"Paris"}"#.to_string(),
+                                    },
+                                }),
+                            ]),
+                        }),
+                        RequestMessage::Tool(ToolMessage {
+                            content: "15 degrees".to_string(),
+                            tool_call_id: "toolu_01".to_string(),
+                        }),
+                        RequestMessage::User(UserMessage {
+                            content: Content::Array(vec![
+                                ContentPart::Text(TextContentPart {
+                                    text: "Thanks".to_string(),
+                                }),
+                            ]),
+                            name: None,
+                        }),
+                    ],
+                    ..Default::default()
+                },
+            ),
+            (
+                "any tool, failed tool result",
+                r#"{
+                "model": "claude-3-opus-20240229",
+                "max_tokens": 1024,
+                "tool_choice": {"type": "any"},
+                "messages": [
+                    {"role": "user", "content": [
+                        {"type": "tool_result", "tool_use_id": "toolu_01", "is_error": true, "content": [
+                            {"type": "text", "text": "location not found"},
+                            {"type": "text", "text": "try a city name"}
+                        ]}
+                    ]}
+                ]
+            }"#,
+                RequestBody {
+                    model: "claude-3-opus-20240229".to_string(),
+                    max_completion_tokens: Some(1024),
+                    tool_choice: Some(OpenaiToolChoice::Required),
+                    messages: vec![
+                        RequestMessage::Tool(ToolMessage {
+                            content: "location not found\ntry a city name".to_string(),
+                            tool_call_id: "toolu_01".to_string(),
+                        }),
+                    ],
+                    ..Default::default()
+                },
+            ),
+            (
+                "images, thinking and documents",
+                r#"{
+                "model": "claude-3-opus-20240229",
+                "max_tokens": 1024,
+                "messages": [
+                    {"role": "user", "content": [
+                        {"type": "text", "text": "Compare these"},
+                        {"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}},
+                        {"type": "document", "source": "doc"}
+                    ]},
+                    {"role": "assistant", "content": [
+                        {"type": "thinking", "thinking": "Need the second image.", "signature": "sig"},
+                        {"type": "tool_use", "id": "toolu_01", "name": "fetch_image", "input": {}}
+                    ]},
+                    {"role": "user", "content": [
+                        {"type": "tool_result", "tool_use_id": "toolu_01", "content": [
+                            {"type": "text", "text": "fetched"},
+                            {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo"}}
+                        ]}
+                    ]}
+                ]
+            }"#,
+                RequestBody {
+                    model: "claude-3-opus-20240229".to_string(),
+                    max_completion_tokens: Some(1024),
+                    messages: vec![
+                        RequestMessage::User(UserMessage {
+                            content: Content::Array(vec![
+                                ContentPart::Text(TextContentPart {
+                                    text: "Compare these".to_string(),
+                                }),
+                                ContentPart::Image(ImageContentPart {
+                                    image_url: ImageUrl {
+                                        url: "https://example.com/a.png".to_string(),
+                                        detail: None,
+                                    },
+                                    dimensions: None,
+                                }),
+                            ]),
+                            name: None,
+                        }),
+                        RequestMessage::Assistant(AssistantMessage {
+                            content: None,
+                            name: None,
+                            tool_calls: Some(vec![
+                                ToolCall::Function(ToolCallFunction {
+                                    id: "toolu_01".to_string(),
+                                    function: ToolCallFunctionObj {
+                                        name: "fetch_image".to_string(),
+                                        arguments: "{}".to_string(),
+                                    },
+                                }),
+                            ]),
+                        }),
+                        RequestMessage::Tool(ToolMessage {
+                            content: "fetched".to_string(),
+                            tool_call_id: "toolu_01".to_string(),
+                        }),
+                        RequestMessage::User(UserMessage {
+                            content: Content::Array(vec![
+                                ContentPart::Image(ImageContentPart {
+                                    image_url: ImageUrl {
+                                        url: "data:image/png;base64,iVBORw0KGgo".to_string(),
+                                        detail: None,
+                                    },
+                                    dimensions: None,
+                                }),
+                            ]),
+                            name: None,
+                        }),
+                    ],
+                    ..Default::default()
+                },
+            ),
+        ];
+        for (name, json, want) in tests {
+            let req: Request = serde_json::from_str(json).unwrap();
+            let got: RequestBody = req.into();
+            assert_eq!(got, want, "convert claude request test failed: {}", name);
+        }
+    }
+
+    #[test]
+    fn conversion_report() {
+        let tests = vec![
+            (
+                "lossless",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hello!"}],"max_completion_tokens":1024}"#,
+                vec![],
+            ),
+            (
+                "request fields",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hello!"}],"max_completion_tokens":1024,"n":2,"seed":42,"logit_bias":{"50256":-100},"response_format":{"type":"json_object"}}"#,
+                vec![
+                    ConversionIssue::dropped("n", "not supported by Claude"),
+                    ConversionIssue::dropped("logit_bias", "not supported by Claude"),
+                    ConversionIssue::dropped("seed", "not supported by Claude"),
+                    ConversionIssue::dropped("response_format", "not supported by Claude"),
+                ],
+            ),
+            (
+                "account fields",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hello!"}],"max_completion_tokens":1024,"user":"user-1","metadata":{"team":"a"},"store":true,"modalities":["text","audio"],"service_tier":"flex"}"#,
+                vec![
+                    ConversionIssue::dropped("metadata", "not supported by Claude"),
+                    ConversionIssue::dropped("store", "not supported by Claude"),
+                    ConversionIssue::dropped("modalities", "not supported by Claude"),
+                    ConversionIssue::dropped("service_tier", "not supported by Claude"),
+                ],
+            ),
+            (
+                "parts",
+                r#"{"model":"gpt-4o","messages":[{"role":"system","content":"first"},{"role":"system","content":"second"},{"role":"user","content":[{"type":"text","text":"What's in this image?"},{"type":"image_url","image_url":{"url":"ftp://example.com/cat.png"}}]},{"role":"assistant","tool_calls":[{"id":"call_1","type":"function","function":{"name":"get_weather","arguments":"{\"location\":"}}]}],"max_completion_tokens":1024}"#,
+                vec![
+                    ConversionIssue::dropped(
+                        "system",
+                        "Claude takes a single system prompt, replaced by a later one",
+                    )
+                    .at_message(0),
+                    ConversionIssue::dropped("image_url", "unsupported image type")
+                        .at_message(2)
+                        .at_part(1),
+                    ConversionIssue::degraded("arguments", "invalid JSON, sent as an empty object")
+                        .at_message(3),
+                ],
+            ),
+        ];
+        for (name, json, want) in tests {
+            let body: RequestBody = serde_json::from_str(json).unwrap();
+            let got = convert_with_report(body.clone(), ConversionMode::Lossy).unwrap();
+            assert_eq!(got.report.issues, want, "lossy test failed: {}", name);
+
+            let strict = convert_with_report(body, ConversionMode::Strict);
+            match want.first() {
+                Some(issue) => assert_eq!(
+                    strict.unwrap_err(),
+                    ConversionError(issue.clone()),
+                    "strict test failed: {}",
+                    name
+                ),
+                None => assert_eq!(
+                    strict.unwrap().request,
+                    got.request,
+                    "strict test failed: {}",
+                    name
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn max_tokens() {
+        let tests = vec![
+            (
+                "max_completion_tokens",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hello!"}],"max_completion_tokens":1024}"#,
+                1024,
+                vec![],
+            ),
+            (
+                "deprecated max_tokens",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hello!"}],"max_tokens":512}"#,
+                512,
+                vec![],
+            ),
+            (
+                "conflicting",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hello!"}],"max_completion_tokens":1024,"max_tokens":512}"#,
+                1024,
+                vec![
+                    ConversionIssue::dropped(
+                        "max_tokens",
+                        "conflicts with max_completion_tokens, which is used",
+                    ),
+                ],
+            ),
+            (
+                "missing",
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hello!"}]}"#,
+                4000,
+                vec![],
+            ),
+        ];
+        for (name, json, want_max_tokens, want_issues) in tests {
+            let body: RequestBody = serde_json::from_str(json).unwrap();
+            let got = convert_with_report(body.clone(), ConversionMode::Lossy).unwrap();
+            assert_eq!(got.request.max_tokens, want_max_tokens, "{}", name);
+            assert_eq!(got.report.issues, want_issues, "{}", name);
+            let strict = convert_with_report(body, ConversionMode::Strict);
+            assert_eq!(strict.is_ok(), want_issues.is_empty(), "strict: {}", name);
+        }
+    }
+
+    #[test]
+    fn convert_response() {
+        let tests = vec![
+            (
+                "text",
+                r#"{"id":"msg_013Zva2CMHLNnXjNJJKqJ2EF","type":"message","role":"assistant","content":[{"type":"text","text":"Hi! My name is Claude."}],"model":"claude-3-opus-20240229","stop_reason":"end_turn","stop_sequence":null,"usage":{"input_tokens":10,"output_tokens":25}}"#,
+                OpenaiResponse {
+                    id: "msg_013Zva2CMHLNnXjNJJKqJ2EF".to_string(),
+                    choices: vec![
+                        OpenaiResponseChoice {
+                            index: 0,
+                            message: OpenaiMessage {
+                                content: Some("Hi! My name is Claude.".to_string()),
+                                role: OpenaiRole::Assistant,
+                                ..Default::default()
+                            },
+                            finish_reason: Some(FinishReason::Stop),
+                            logprobs: None,
+                        },
+                    ],
+                    model: "claude-3-opus-20240229".to_string(),
+                    object: "chat.completion".to_string(),
+                    usage: Usage {
+                        completion_tokens: 25,
+                        prompt_tokens: 10,
+                        total_tokens: 35,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ),
+            (
+                "thinking and tool use with cache",
+                r#"{"id":"msg_01Aq9w938a90dw8q","type":"message","role":"assistant","content":[{"type":"thinking","thinking":"The user wants the weather.","signature":"EqQBCgIYAhIM"},{"type":"text","text":"Let me check."},{"type":"tool_use","id":"toolu_01A09q90qw90lq917835lq9","name":"get_weather","input":{"location":"San Francisco, CA"}}],"model":"claude-3-7-sonnet-20250219","stop_reason":"tool_use","stop_sequence":null,"usage":{"input_tokens":20,"output_tokens":40,"cache_creation_input_tokens":100,"cache_read_input_tokens":1000}}"#,
+                OpenaiResponse {
+                    id: "msg_01Aq9w938a90dw8q".to_string(),
+                    choices: vec![
+                        OpenaiResponseChoice {
+                            index: 0,
+                            message: OpenaiMessage {
+                                content: Some("Let me check.".to_string()),
+                                reasoning: Some("The user wants the weather.".to_string()),
+                                tool_calls: Some(vec![
+                                    ToolCall::Function(ToolCallFunction {
+                                        id: "toolu_01A09q90qw90lq917835lq9".to_string(),
+                                        function: ToolCallFunctionObj {
+                                            name: "get_weather".to_string(),
+                                            arguments: r#"{"location":"San Francisco, CA"}"#
+                                                .to_string(),
+                                        },
+                                    }),
+                                ]),
+                                role: OpenaiRole::Assistant,
+                                ..Default::default()
+                            },
+                            finish_reason: Some(FinishReason::ToolCalls),
+                            logprobs: None,
+                        },
+                    ],
+                    model: "claude-3-7-sonnet-20250219".to_string(),
+                    object: "chat.completion".to_string(),
+                    usage: Usage {
+                        completion_tokens: 40,
+                        prompt_tokens: 1120,
+                        total_tokens: 1160,
+                        prompt_tokens_details: Some(PromptTokensDetails {
+                            cached_tokens: Some(1000),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ),
+        ];
+        for (name, json, want) in tests {
+            let res: async_claude::messages::Response = serde_json::from_str(json).unwrap();
+            let mut got: OpenaiResponse = res.into();
+            got.created = 0;
+            assert_eq!(got, want, "convert response test failed: {}", name);
+        }
+    }
+
     #[test]
     fn test_process_stream_events() {
         let events = vec![
@@ -633,6 +1874,7 @@ mod tests {
                 async_claude::messages::ResponseContentBlock::Base(
                     async_claude::messages::BaseContentBlock::Text {
                         text: "Hello!".to_string(),
+                        cache_control: None,
                     },
                 ),
             ],
@@ -642,6 +1884,7 @@ mod tests {
             usage: async_claude::messages::Usage {
                 input_tokens: Some(25),
                 output_tokens: 16,
+                ..Default::default()
             },
         };
         assert_eq!(
@@ -927,6 +2170,7 @@ mod tests {
                 async_claude::messages::ResponseContentBlock::Base(
                     async_claude::messages::BaseContentBlock::Text {
                         text: "Okay, let's check the weather for San Francisco, CA:".to_string(),
+                        cache_control: None,
                     },
                 ),
                 async_claude::messages::ResponseContentBlock::Base(
@@ -948,6 +2192,7 @@ mod tests {
             usage: async_claude::messages::Usage {
                 input_tokens: Some(472),
                 output_tokens: 91,
+                ..Default::default()
             },
         };
         assert_eq!(
@@ -1006,4 +2251,372 @@ mod tests {
             "OpenAI unary response doesn't match expected value"
         );
     }
+
+    #[test]
+    fn cache_usage() {
+        let events = vec![
+            r#"{"type":"message_start","message":{"id":"msg_01","type":"message","role":"assistant","model":"claude-3-7-sonnet-20250219","content":[],"stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":10,"cache_creation_input_tokens":200,"cache_read_input_tokens":1000,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn","stop_sequence":null},"usage":{"output_tokens":5}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let mut parser = ClaudeEventDataParser::default();
+        for event in events {
+            parser.parse_str(event).unwrap();
+        }
+        let claude_usage = parser.claude_response().usage;
+        assert_eq!(claude_usage.input_tokens, Some(10));
+        assert_eq!(claude_usage.cache_creation_input_tokens, Some(200));
+        assert_eq!(claude_usage.cache_read_input_tokens, Some(1000));
+        assert_eq!(claude_usage.output_tokens, 6);
+        let usage = parser.response().usage;
+        assert_eq!(usage.prompt_tokens, 1210);
+        assert_eq!(usage.completion_tokens, 6);
+        assert_eq!(usage.total_tokens, 1216);
+        assert_eq!(
+            usage.prompt_tokens_details.and_then(|d| d.cached_tokens),
+            Some(1000)
+        );
+    }
+
+    #[test]
+    fn redacted_thinking() {
+        let events = vec![
+            r#"{"type":"message_start","message":{"id":"msg_01","type":"message","role":"assistant","model":"claude-3-7-sonnet-20250219","content":[],"stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":10,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":"","signature":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"I should call the tool."}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"EqQBCgIYAhIM"}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"redacted_thinking","data":"EmwKAhgBEgy3va3pzix"}}"#,
+            r#"{"type":"content_block_stop","index":1}"#,
+            r#"{"type":"content_block_start","index":2,"content_block":{"type":"tool_use","id":"toolu_01","name":"get_weather","input":{}}}"#,
+            r#"{"type":"content_block_delta","index":2,"delta":{"type":"input_json_delta","partial_json":"{\"location\": \"Paris\"}"}}"#,
+            r#"{"type":"content_block_stop","index":2}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use","stop_sequence":null},"usage":{"output_tokens":20}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let mut parser = ClaudeEventDataParser::default();
+        for event in events {
+            parser.parse_str(event).unwrap();
+        }
+        let content = parser.claude_response().content;
+        assert_eq!(
+            serde_json::to_value(&content).unwrap(),
+            serde_json::json!([
+                {"type": "thinking", "thinking": "I should call the tool.", "signature": "EqQBCgIYAhIM"},
+                {"type": "redacted_thinking", "data": "EmwKAhgBEgy3va3pzix"},
+                {"type": "tool_use", "id": "toolu_01", "name": "get_weather", "input": {"location": "Paris"}}
+            ])
+        );
+        // the assistant turn of the next request
+        let blocks: Vec<ContentBlock> =
+            serde_json::from_value(serde_json::to_value(&content).unwrap()).unwrap();
+        assert!(matches!(
+            blocks[1],
+            ContentBlock::RedactedThinking(RedactedThinkingContentBlock::RedactedThinking { .. })
+        ));
+    }
+
+    #[test]
+    fn interleaved_blocks() {
+        let events = vec![
+            r#"{"type":"message_start","message":{"id":"msg_01","type":"message","role":"assistant","model":"claude-sonnet-4-20250514","content":[],"stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":10,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":"","signature":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Check the weather first."}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"sig_0"}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"Checking Paris."}}"#,
+            r#"{"type":"content_block_stop","index":1}"#,
+            r#"{"type":"content_block_start","index":2,"content_block":{"type":"tool_use","id":"toolu_01","name":"get_weather","input":{}}}"#,
+            r#"{"type":"content_block_delta","index":2,"delta":{"type":"input_json_delta","partial_json":"{\"location\": \"Paris\"}"}}"#,
+            r#"{"type":"content_block_stop","index":2}"#,
+            r#"{"type":"content_block_start","index":3,"content_block":{"type":"thinking","thinking":"","signature":""}}"#,
+            r#"{"type":"content_block_delta","index":3,"delta":{"type":"thinking_delta","thinking":"Now the time."}}"#,
+            r#"{"type":"content_block_delta","index":3,"delta":{"type":"signature_delta","signature":"sig_3"}}"#,
+            r#"{"type":"content_block_stop","index":3}"#,
+            r#"{"type":"content_block_start","index":4,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":4,"delta":{"type":"text_delta","text":"And the time."}}"#,
+            r#"{"type":"content_block_stop","index":4}"#,
+            r#"{"type":"content_block_start","index":5,"content_block":{"type":"tool_use","id":"toolu_02","name":"get_time","input":{}}}"#,
+            r#"{"type":"content_block_stop","index":5}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use","stop_sequence":null},"usage":{"output_tokens":40}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let mut parser = ClaudeEventDataParser::default();
+        for event in events {
+            // the blocks are all part of the one choice
+            if let (Some(Chunk::Data(chunk)), _) = parser.parse_str(event).unwrap() {
+                assert_eq!(chunk.choices[0].index, 0, "{}", event);
+            }
+        }
+        assert_eq!(
+            serde_json::to_value(parser.claude_response().content).unwrap(),
+            serde_json::json!([
+                {"type": "thinking", "thinking": "Check the weather first.", "signature": "sig_0"},
+                {"type": "text", "text": "Checking Paris."},
+                {"type": "tool_use", "id": "toolu_01", "name": "get_weather", "input": {"location": "Paris"}},
+                {"type": "thinking", "thinking": "Now the time.", "signature": "sig_3"},
+                {"type": "text", "text": "And the time."},
+                {"type": "tool_use", "id": "toolu_02", "name": "get_time", "input": {}}
+            ])
+        );
+        let res = parser.response();
+        assert_eq!(
+            res.choices[0]
+                .message
+                .tool_calls
+                .as_ref()
+                .map(|calls| calls.len()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn stop_sequence() {
+        let events = vec![
+            r#"{"type":"message_start","message":{"id":"msg_01","type":"message","role":"assistant","model":"claude-sonnet-4-20250514","content":[],"stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":10,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"1, 2, 3"}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"stop_sequence","stop_sequence":"4"},"usage":{"output_tokens":8}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let mut parser = ClaudeEventDataParser::default();
+        for event in events {
+            parser.parse_str(event).unwrap();
+        }
+        let res = parser.claude_response();
+        assert_eq!(res.stop_reason, Some(StopReason::StopSequence));
+        assert_eq!(res.stop_sequence.as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn invalid_tool_input() {
+        let tool_call = ToolCall::Function(ToolCallFunction {
+            id: "toolu_01".to_string(),
+            function: ToolCallFunctionObj {
+                name: "get_weather".to_string(),
+                arguments: r#"{"location": "Par"#.to_string(),
+            },
+        });
+        let events = vec![
+            (
+                "start",
+                r#"{"type":"message_start","message":{"id":"msg_01","type":"message","role":"assistant","model":"claude-sonnet-4-20250514","content":[],"stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":10,"output_tokens":1}}}"#,
+                None,
+            ),
+            (
+                "tool_use start",
+                r#"{"type":"content_block_start","index":0,"content_block":{"type":"tool_use","id":"toolu_01","name":"get_weather","input":{}}}"#,
+                None,
+            ),
+            (
+                "truncated input",
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"location\": \"Par"}}"#,
+                None,
+            ),
+            (
+                "tool_use stop",
+                r#"{"type":"content_block_stop","index":0}"#,
+                Some(tool_call.clone()),
+            ),
+            (
+                "delta",
+                r#"{"type":"message_delta","delta":{"stop_reason":"tool_use","stop_sequence":null},"usage":{"output_tokens":8}}"#,
+                None,
+            ),
+            ("stop", r#"{"type":"message_stop"}"#, None),
+        ];
+        let mut parser = ClaudeEventDataParser::default();
+        for (name, event, want) in events {
+            let (_, got) = parser.parse_str(event).unwrap();
+            assert_eq!(got, want, "{}", name);
+        }
+        assert_eq!(
+            serde_json::to_value(parser.claude_response().content).unwrap(),
+            serde_json::json!([
+                {"type": "tool_use", "id": "toolu_01", "name": "get_weather", "input": {}}
+            ])
+        );
+        let res = parser.response();
+        assert_eq!(res.choices[0].message.tool_calls, Some(vec![tool_call]));
+    }
+
+    #[test]
+    fn error_event() {
+        let tests = vec![
+            (
+                "overloaded",
+                r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#,
+                "Error from Claude API: OverloadedError: Overloaded",
+                OpenaiErr {
+                    message: "Overloaded".to_string(),
+                    r#type: "overloaded_error".to_string(),
+                    param: "".to_string(),
+                    code: "529".to_string(),
+                },
+            ),
+            (
+                "rate limit",
+                r#"{"type": "error", "error": {"type": "rate_limit_error", "message": "Too many requests"}}"#,
+                "Error from Claude API: RateLimitError: Too many requests",
+                OpenaiErr {
+                    message: "Too many requests".to_string(),
+                    r#type: "rate_limit_error".to_string(),
+                    param: "".to_string(),
+                    code: "429".to_string(),
+                },
+            ),
+            (
+                "unknown",
+                r#"{"type": "error", "error": {"type": "billing_error", "message": "Credit balance is too low"}}"#,
+                "Error from Claude API: billing_error: Credit balance is too low",
+                OpenaiErr {
+                    message: "Credit balance is too low".to_string(),
+                    r#type: "billing_error".to_string(),
+                    param: "".to_string(),
+                    code: "".to_string(),
+                },
+            ),
+        ];
+        for (name, event, want_message, want_err) in tests {
+            let mut parser = ClaudeEventDataParser::default();
+            let err = parser.parse_str(event).unwrap_err();
+            assert_eq!(err.to_string(), want_message, "{}", name);
+            assert_eq!(err.downcast_ref::<OpenaiErr>(), Some(&want_err), "{}", name);
+        }
+    }
+
+    #[test]
+    fn unknown_events() {
+        let events = vec![
+            r#"{"type":"message_start","message":{"id":"msg_01","type":"message","role":"assistant","model":"claude-sonnet-4-20250514","content":[],"stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":10,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"I can't"}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"citations_delta","citation":{"type":"char_location","cited_text":"Paris"}}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"message_summary","summary":"refused"}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"refusal","stop_sequence":null},"usage":{"output_tokens":5}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let mut parser = ClaudeEventDataParser::default();
+        for event in events {
+            parser.parse_str(event).unwrap();
+        }
+        let res = parser.response();
+        assert_eq!(res.choices[0].message.content.as_deref(), Some("I can't"));
+        assert_eq!(
+            res.choices[0].finish_reason,
+            Some(FinishReason::ContentFilter)
+        );
+    }
+
+    #[test]
+    fn partial_tool_call() {
+        let test_events = vec![
+            (
+                r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_01","name":"get_weather","input":{}}}"#,
+                Some((serde_json::Value::Null, vec![""])),
+            ),
+            (
+                r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"location\":"}}"#,
+                Some((
+                    serde_json::json!({}),
+                    vec![
+                        "",
+                        "/location",
+                    ],
+                )),
+            ),
+            (
+                r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":" \"San Francisco, CA\"}"}}"#,
+                Some((serde_json::json!({"location": "San Francisco, CA"}), vec![])),
+            ),
+            (r#"{"type":"content_block_stop","index":1}"#, None),
+        ];
+        let mut parser = ClaudeEventDataParser::default();
+        for (event_str, want) in test_events {
+            parser.parse_str(event_str).unwrap();
+            let got = parser.partial_tool_call().map(|call| {
+                assert_eq!(call.id, "toolu_01");
+                let arguments = call.arguments.unwrap();
+                (arguments.value, arguments.incomplete)
+            });
+            let want = want.map(|(value, incomplete)| {
+                (value, incomplete.into_iter().map(String::from).collect())
+            });
+            assert_eq!(got, want, "partial tool call failed: {}", event_str);
+        }
+    }
+
+    #[test]
+    fn decode_sse() {
+        let body = "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"type\":\"message\",\"role\":\"assistant\",\"content\":[],\"model\":\"claude-3-haiku-20240307\",\"stop_reason\":null,\"stop_sequence\":null,\"usage\":{\"input_tokens\":10,\"output_tokens\":1}}}\n\nevent: ping\ndata: {\"type\": \"ping\"}\n\nevent: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\nevent: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\nevent: message_stop\ndata: {\"type\":\"message_stop\"}\n\n";
+        let mut decoder = crate::sse::EventDecoder::<(EventName, EventData)>::new();
+        let mut parser = ClaudeEventDataParser::default();
+        let mut names = vec![];
+        // split the body to exercise the partial lines
+        for bytes in body.as_bytes().chunks(7) {
+            for item in decoder.push(bytes) {
+                let (name, data) = item.unwrap();
+                parser.parse(&data).unwrap();
+                names.push(name);
+            }
+        }
+        assert_eq!(
+            names,
+            vec![
+                EventName::MessageStart,
+                EventName::Ping,
+                EventName::ContentBlockStart,
+                EventName::ContentBlockDelta,
+                EventName::MessageStop,
+            ]
+        );
+        assert_eq!(
+            parser.response().choices[0].message.content.as_deref(),
+            Some("Hello")
+        );
+    }
+
+    #[test]
+    fn encode_sse() {
+        use crate::sse::ToSseEvent;
+
+        let tests = vec![
+            (
+                EventData::MessageStop,
+                "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+            ),
+            (
+                EventData::Error {
+                    error: async_claude::messages::ErrorData::OverloadedError {
+                        message: "Overloaded".to_string(),
+                    },
+                },
+                "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n",
+            ),
+        ];
+        for (data, want) in tests {
+            assert_eq!(data.to_sse_event().unwrap().to_string(), want);
+        }
+    }
+
+    #[cfg(feature = "claude-price")]
+    #[test]
+    fn price_cached_tokens() {
+        let usage: Usage = async_claude::messages::Usage {
+            input_tokens: Some(100),
+            output_tokens: 0,
+            cache_read_input_tokens: Some(1000),
+            ..Default::default()
+        }
+        .into();
+        // 100 tokens at the input price, 1000 at a tenth of it
+        let got = super::price("claude-3-sonnet-20240229", &usage);
+        assert!((got - 0.0006).abs() < 1e-7, "got {}", got);
+    }
 }
@@ -0,0 +1,722 @@
+use std::collections::HashMap;
+
+use crate::entity::{
+    chat_completion_chunk::{Chunk, ChunkResponse, ToolCallChunk},
+    chat_completion_object::Usage as OpenaiUsage,
+    create_chat_completion::FinishReason,
+};
+
+use super::{
+    BaseContentBlock, DeltaContentBlock, ErrorData, EventData, MessageDelta, Response,
+    ResponseContentBlock, Role, StopReason, ToolUseContentBlock, Usage,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Text,
+    Thinking,
+    /// `index` is the Openai tool call index.
+    ToolUse {
+        index: usize,
+    },
+}
+
+/// A tool call whose fragments arrived while another tool call block was open.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct PendingToolCall {
+    index: usize,
+    id: Option<String>,
+    name: Option<String>,
+    arguments: String,
+}
+
+/// OpenaiToClaudeEventEncoder is the mirror of `ClaudeEventDataParser`, it converts Openai's chunks to Claude's event data,
+/// so Anthropic-SDK clients can be served from an Openai compatible upstream.
+///
+/// Every text, reasoning or tool call run becomes its own content block,
+/// reasoning is sent as `thinking_delta` and tool call arguments as `input_json_delta`.
+/// Claude's blocks can't interleave, so the arguments of parallel tool calls streamed while another call is open
+/// are buffered and each of those calls is sent as one block when the choice finishes.
+/// A new id on a tool call index already in use starts another call, the same as `OpenaiEventDataParser`.
+/// Arguments for a call whose block was already closed can't be sent, the stream then ends with an `error` event.
+/// Refusals are sent as text, the stop reason tells them apart.
+/// `message_delta` is sent once the finish reason and the usage are known, `message_stop` when `[DONE]` arrives.
+/// Only the first choice is encoded.
+///
+/// # Example
+///
+/// ````
+/// use await_openai::claude::{EventData, OpenaiToClaudeEventEncoder};
+/// use await_openai::entity::chat_completion_chunk::Chunk;
+///
+/// let mut encoder = OpenaiToClaudeEventEncoder::default();
+/// let chunk: Chunk = r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","content":"Hi"}}]}"#.parse().unwrap();
+/// let events = encoder.encode(&chunk);
+/// assert!(matches!(events[0], EventData::MessageStart { .. }));
+/// ````
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpenaiToClaudeEventEncoder {
+    started: bool,
+    delta_sent: bool,
+    stopped: bool,
+    next_index: u32,
+    current: Option<(u32, Block)>,
+    /// Openai tool call index to the Claude block index of the calls already started.
+    tool_blocks: HashMap<usize, u32>,
+    /// Openai tool call index to the id of the latest call on it.
+    tool_ids: HashMap<usize, String>,
+    pending_tool_calls: Vec<PendingToolCall>,
+    finish_reason: Option<FinishReason>,
+    usage: Option<OpenaiUsage>,
+}
+
+impl OpenaiToClaudeEventEncoder {
+    /// Encode a chunk into zero or more Claude events.
+    pub fn encode(&mut self, chunk: &Chunk) -> Vec<EventData> {
+        match chunk {
+            Chunk::Done => self.finish(),
+            Chunk::Data(res) => self.encode_response(res),
+        }
+    }
+
+    /// Close the stream, the same as receiving `[DONE]`.
+    pub fn finish(&mut self) -> Vec<EventData> {
+        let mut events = vec![];
+        if !self.started || self.stopped {
+            return events;
+        }
+        self.close_block(&mut events);
+        self.flush_tool_calls(&mut events);
+        self.message_delta(&mut events);
+        events.push(EventData::MessageStop);
+        self.stopped = true;
+        events
+    }
+
+    fn encode_response(&mut self, res: &ChunkResponse) -> Vec<EventData> {
+        let mut events = vec![];
+        if self.stopped {
+            return events;
+        }
+        if !self.started {
+            self.started = true;
+            events.push(EventData::MessageStart {
+                message: Response {
+                    id: res.id.clone(),
+                    r#type: "message".to_string(),
+                    role: Role::Assistant,
+                    content: vec![],
+                    model: res.model.clone(),
+                    stop_reason: None,
+                    stop_sequence: None,
+                    usage: claude_usage(res.usage.as_ref()),
+                },
+            });
+        }
+        if let Some(usage) = &res.usage {
+            self.usage = Some(usage.clone());
+        }
+
+        if let Some(choice) = res.choices.iter().find(|c| c.index == 0) {
+            if let Some(reasoning) = choice.delta.reasoning.as_deref().filter(|s| !s.is_empty()) {
+                let index = self.ensure_block(Block::Thinking, &mut events);
+                events.push(EventData::ContentBlockDelta {
+                    index,
+                    delta: DeltaContentBlock::ThinkingDelta {
+                        thinking: reasoning.to_string(),
+                    },
+                });
+            }
+            if let Some(content) = choice.delta.content.as_deref().filter(|s| !s.is_empty()) {
+                let index = self.ensure_block(Block::Text, &mut events);
+                events.push(EventData::ContentBlockDelta {
+                    index,
+                    delta: DeltaContentBlock::TextDelta {
+                        text: content.to_string(),
+                    },
+                });
+            }
+            if let Some(refusal) = choice.delta.refusal.as_deref().filter(|s| !s.is_empty()) {
+                let index = self.ensure_block(Block::Text, &mut events);
+                events.push(EventData::ContentBlockDelta {
+                    index,
+                    delta: DeltaContentBlock::TextDelta {
+                        text: refusal.to_string(),
+                    },
+                });
+            }
+            for tool_call in choice.delta.tool_calls.iter().flatten() {
+                self.encode_tool_call(tool_call, &mut events);
+                if self.stopped {
+                    return events;
+                }
+            }
+            if let Some(finish_reason) = choice.finish_reason {
+                self.finish_reason = Some(finish_reason);
+                self.close_block(&mut events);
+                self.flush_tool_calls(&mut events);
+            }
+        }
+
+        if self.finish_reason.is_some() && self.usage.is_some() {
+            self.message_delta(&mut events);
+        }
+        events
+    }
+
+    fn encode_tool_call(&mut self, tool_call: &ToolCallChunk, events: &mut Vec<EventData>) {
+        let mut new_call = false;
+        if let Some(id) = &tool_call.id
+            && let Some(previous) = self.tool_ids.insert(tool_call.index, id.clone())
+            && previous != *id
+        {
+            // the previous call on this index is complete
+            new_call = true;
+            if matches!(self.current, Some((_, Block::ToolUse { index })) if index == tool_call.index)
+            {
+                self.close_block(events);
+            }
+            self.tool_blocks.remove(&tool_call.index);
+        }
+        let open = match &self.current {
+            Some((block_index, Block::ToolUse { index })) => Some((*block_index, *index)),
+            _ => None,
+        };
+        let arguments = &tool_call.function.arguments;
+        match open {
+            Some((block_index, index)) if index == tool_call.index => {
+                self.input_json_delta(block_index, arguments, events)
+            }
+            // Claude's blocks can't be reopened
+            _ if self.tool_blocks.contains_key(&tool_call.index) => {
+                self.close_block(events);
+                events.push(EventData::Error {
+                    error: ErrorData::ApiError {
+                        message: format!(
+                            "arguments of tool call {} arrived after its block was closed",
+                            tool_call.index
+                        ),
+                    },
+                });
+                self.stopped = true;
+            }
+            // another call is streaming, keep this one until the choice finishes
+            Some(_) => {
+                let position = self
+                    .pending_tool_calls
+                    .iter()
+                    .rposition(|p| p.index == tool_call.index)
+                    .filter(|_| !new_call);
+                let pending = match position {
+                    Some(i) => &mut self.pending_tool_calls[i],
+                    None => {
+                        self.pending_tool_calls.push(PendingToolCall {
+                            index: tool_call.index,
+                            ..Default::default()
+                        });
+                        self.pending_tool_calls.last_mut().unwrap()
+                    }
+                };
+                if pending.id.is_none() {
+                    pending.id = tool_call.id.clone();
+                }
+                if pending.name.is_none() {
+                    pending.name = tool_call.function.name.clone();
+                }
+                pending.arguments.push_str(arguments);
+            }
+            None => {
+                let block_index = self.start_tool_use(
+                    tool_call.index,
+                    tool_call.id.clone(),
+                    tool_call.function.name.clone(),
+                    events,
+                );
+                self.input_json_delta(block_index, arguments, events);
+            }
+        }
+    }
+
+    fn start_tool_use(
+        &mut self,
+        index: usize,
+        id: Option<String>,
+        name: Option<String>,
+        events: &mut Vec<EventData>,
+    ) -> u32 {
+        self.close_block(events);
+        let block_index = self.next_index;
+        self.next_index += 1;
+        self.current = Some((block_index, Block::ToolUse { index }));
+        self.tool_blocks.insert(index, block_index);
+        events.push(EventData::ContentBlockStart {
+            index: block_index,
+            content_block: ResponseContentBlock::Base(BaseContentBlock::ToolUse(
+                ToolUseContentBlock {
+                    id: id.unwrap_or_default(),
+                    name: name.unwrap_or_default(),
+                    input: serde_json::json!({}),
+                },
+            )),
+        });
+        block_index
+    }
+
+    fn input_json_delta(&self, index: u32, arguments: &str, events: &mut Vec<EventData>) {
+        if !arguments.is_empty() {
+            events.push(EventData::ContentBlockDelta {
+                index,
+                delta: DeltaContentBlock::InputJsonDelta {
+                    partial_json: arguments.to_string(),
+                },
+            });
+        }
+    }
+
+    /// Send each buffered tool call as a whole block.
+    fn flush_tool_calls(&mut self, events: &mut Vec<EventData>) {
+        for pending in std::mem::take(&mut self.pending_tool_calls) {
+            let block_index = self.start_tool_use(pending.index, pending.id, pending.name, events);
+            self.input_json_delta(block_index, &pending.arguments, events);
+            self.close_block(events);
+        }
+    }
+
+    /// Start a new content block unless the open one is already of the given kind, return its index.
+    fn ensure_block(&mut self, block: Block, events: &mut Vec<EventData>) -> u32 {
+        if let Some((index, current)) = &self.current
+            && *current == block
+        {
+            return *index;
+        }
+        self.close_block(events);
+        let index = self.next_index;
+        self.next_index += 1;
+        let content_block = match block {
+            Block::Thinking => BaseContentBlock::Thinking {
+                thinking: String::new(),
+                signature: None,
+            },
+            _ => BaseContentBlock::Text {
+                text: String::new(),
+                cache_control: None,
+            },
+        };
+        self.current = Some((index, block));
+        events.push(EventData::ContentBlockStart {
+            index,
+            content_block: ResponseContentBlock::Base(content_block),
+        });
+        index
+    }
+
+    fn close_block(&mut self, events: &mut Vec<EventData>) {
+        if let Some((index, _)) = self.current.take() {
+            events.push(EventData::ContentBlockStop { index });
+        }
+    }
+
+    fn message_delta(&mut self, events: &mut Vec<EventData>) {
+        if self.delta_sent {
+            return;
+        }
+        self.delta_sent = true;
+        events.push(EventData::MessageDelta {
+            delta: MessageDelta {
+                stop_reason: self
+                    .finish_reason
+                    .map(StopReason::from)
+                    .unwrap_or(StopReason::EndTurn),
+                stop_sequence: None,
+            },
+            usage: claude_usage(self.usage.as_ref()),
+        });
+    }
+}
+
+/// Claude clients expect integer token counts, unknown usage is sent as zero.
+fn claude_usage(usage: Option<&OpenaiUsage>) -> Usage {
+    match usage {
+        Some(usage) => Usage::from(usage.clone()),
+        None => Usage {
+            input_tokens: Some(0),
+            ..Default::default()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::chat_completion_chunk::Chunk;
+
+    use super::*;
+
+    #[test]
+    fn encode() {
+        let zero_usage = Usage {
+            input_tokens: Some(0),
+            ..Default::default()
+        };
+        let start = |id: &str| EventData::MessageStart {
+            message: Response {
+                id: id.to_string(),
+                r#type: "message".to_string(),
+                role: Role::Assistant,
+                content: vec![],
+                model: "gpt-4o".to_string(),
+                stop_reason: None,
+                stop_sequence: None,
+                usage: zero_usage.clone(),
+            },
+        };
+        let tool_use = |index: u32, id: &str, name: &str| EventData::ContentBlockStart {
+            index,
+            content_block: ResponseContentBlock::Base(BaseContentBlock::ToolUse(
+                ToolUseContentBlock {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    input: serde_json::json!({}),
+                },
+            )),
+        };
+        let input_json = |index: u32, partial_json: &str| EventData::ContentBlockDelta {
+            index,
+            delta: DeltaContentBlock::InputJsonDelta {
+                partial_json: partial_json.to_string(),
+            },
+        };
+        let tests = vec![
+            (
+                "text with usage",
+                vec![
+                    r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","content":""}}]}"#,
+                    r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":"Hello"}}]}"#,
+                    r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":" world"}}]}"#,
+                    r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#,
+                    r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[],"usage":{"prompt_tokens":10,"completion_tokens":2,"total_tokens":12}}"#,
+                    "[DONE]",
+                ],
+                vec![
+                    EventData::MessageStart {
+                        message: Response {
+                            id: "chatcmpl-123".to_string(),
+                            r#type: "message".to_string(),
+                            role: Role::Assistant,
+                            content: vec![],
+                            model: "gpt-4o".to_string(),
+                            stop_reason: None,
+                            stop_sequence: None,
+                            usage: zero_usage.clone(),
+                        },
+                    },
+                    EventData::ContentBlockStart {
+                        index: 0,
+                        content_block: ResponseContentBlock::Base(BaseContentBlock::Text {
+                            text: String::new(),
+                            cache_control: None,
+                        }),
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 0,
+                        delta: DeltaContentBlock::TextDelta {
+                            text: "Hello".to_string(),
+                        },
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 0,
+                        delta: DeltaContentBlock::TextDelta {
+                            text: " world".to_string(),
+                        },
+                    },
+                    EventData::ContentBlockStop { index: 0 },
+                    EventData::MessageDelta {
+                        delta: MessageDelta {
+                            stop_reason: StopReason::EndTurn,
+                            stop_sequence: None,
+                        },
+                        usage: Usage {
+                            input_tokens: Some(10),
+                            output_tokens: 2,
+                            ..Default::default()
+                        },
+                    },
+                    EventData::MessageStop,
+                ],
+            ),
+            (
+                "reasoning and tool calls",
+                vec![
+                    r#"{"id":"chatcmpl-456","object":"chat.completion.chunk","created":1694268190,"model":"o3","choices":[{"index":0,"delta":{"role":"assistant","reasoning":"Need the weather."}}]}"#,
+                    r#"{"id":"chatcmpl-456","object":"chat.completion.chunk","created":1694268190,"model":"o3","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"get_weather","arguments":""}}]}}]}"#,
+                    r#"{"id":"chatcmpl-456","object":"chat.completion.chunk","created":1694268190,"model":"o3","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"location\":"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-456","object":"chat.completion.chunk","created":1694268190,"model":"o3","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"Paris\"}"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-456","object":"chat.completion.chunk","created":1694268190,"model":"o3","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"id":"call_2","type":"function","function":{"name":"get_time","arguments":"{}"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-456","object":"chat.completion.chunk","created":1694268190,"model":"o3","choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#,
+                    "[DONE]",
+                ],
+                vec![
+                    EventData::MessageStart {
+                        message: Response {
+                            id: "chatcmpl-456".to_string(),
+                            r#type: "message".to_string(),
+                            role: Role::Assistant,
+                            content: vec![],
+                            model: "o3".to_string(),
+                            stop_reason: None,
+                            stop_sequence: None,
+                            usage: zero_usage.clone(),
+                        },
+                    },
+                    EventData::ContentBlockStart {
+                        index: 0,
+                        content_block: ResponseContentBlock::Base(BaseContentBlock::Thinking {
+                            thinking: String::new(),
+                            signature: None,
+                        }),
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 0,
+                        delta: DeltaContentBlock::ThinkingDelta {
+                            thinking: "Need the weather.".to_string(),
+                        },
+                    },
+                    EventData::ContentBlockStop { index: 0 },
+                    EventData::ContentBlockStart {
+                        index: 1,
+                        content_block: ResponseContentBlock::Base(BaseContentBlock::ToolUse(
+                            ToolUseContentBlock {
+                                id: "call_1".to_string(),
+                                name: "get_weather".to_string(),
+                                input: serde_json::json!({}),
+                            },
+                        )),
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 1,
+                        delta: DeltaContentBlock::InputJsonDelta {
+                            partial_json: r#"{"location":"#.to_string(),
+                        },
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 1,
+                        delta: DeltaContentBlock::InputJsonDelta {
+                            partial_json: r#""Paris"}"#.to_string(),
+                        },
+                    },
+                    EventData::ContentBlockStop { index: 1 },
+                    EventData::ContentBlockStart {
+                        index: 2,
+                        content_block: ResponseContentBlock::Base(BaseContentBlock::ToolUse(
+                            ToolUseContentBlock {
+                                id: "call_2".to_string(),
+                                name: "get_time".to_string(),
+                                input: serde_json::json!({}),
+                            },
+                        )),
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 2,
+                        delta: DeltaContentBlock::InputJsonDelta {
+                            partial_json: "{}".to_string(),
+                        },
+                    },
+                    EventData::ContentBlockStop { index: 2 },
+                    EventData::MessageDelta {
+                        delta: MessageDelta {
+                            stop_reason: StopReason::ToolUse,
+                            stop_sequence: None,
+                        },
+                        usage: zero_usage.clone(),
+                    },
+                    EventData::MessageStop,
+                ],
+            ),
+            (
+                "interleaved tool calls",
+                vec![
+                    r#"{"id":"chatcmpl-789","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"get_weather","arguments":""}}]}}]}"#,
+                    r#"{"id":"chatcmpl-789","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"id":"call_2","type":"function","function":{"name":"get_time","arguments":""}}]}}]}"#,
+                    r#"{"id":"chatcmpl-789","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"location\":"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-789","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"{\"zone\":"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-789","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"Paris\"}"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-789","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"\"CET\"}"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-789","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#,
+                    "[DONE]",
+                ],
+                vec![
+                    EventData::MessageStart {
+                        message: Response {
+                            id: "chatcmpl-789".to_string(),
+                            r#type: "message".to_string(),
+                            role: Role::Assistant,
+                            content: vec![],
+                            model: "gpt-4o".to_string(),
+                            stop_reason: None,
+                            stop_sequence: None,
+                            usage: zero_usage.clone(),
+                        },
+                    },
+                    EventData::ContentBlockStart {
+                        index: 0,
+                        content_block: ResponseContentBlock::Base(BaseContentBlock::ToolUse(
+                            ToolUseContentBlock {
+                                id: "call_1".to_string(),
+                                name: "get_weather".to_string(),
+                                input: serde_json::json!({}),
+                            },
+                        )),
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 0,
+                        delta: DeltaContentBlock::InputJsonDelta {
+                            partial_json: r#"{"location":"#.to_string(),
+                        },
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 0,
+                        delta: DeltaContentBlock::InputJsonDelta {
+                            partial_json: r#""Paris"}"#.to_string(),
+                        },
+                    },
+                    EventData::ContentBlockStop { index: 0 },
+                    EventData::ContentBlockStart {
+                        index: 1,
+                        content_block: ResponseContentBlock::Base(BaseContentBlock::ToolUse(
+                            ToolUseContentBlock {
+                                id: "call_2".to_string(),
+                                name: "get_time".to_string(),
+                                input: serde_json::json!({}),
+                            },
+                        )),
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 1,
+                        delta: DeltaContentBlock::InputJsonDelta {
+                            partial_json: r#"{"zone":"CET"}"#.to_string(),
+                        },
+                    },
+                    EventData::ContentBlockStop { index: 1 },
+                    EventData::MessageDelta {
+                        delta: MessageDelta {
+                            stop_reason: StopReason::ToolUse,
+                            stop_sequence: None,
+                        },
+                        usage: zero_usage.clone(),
+                    },
+                    EventData::MessageStop,
+                ],
+            ),
+            (
+                "tool call index reused",
+                vec![
+                    r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","tool_calls":[{"index":0,"id":"call_a","type":"function","function":{"name":"get_a","arguments":"{\"a\":1}"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_b","type":"function","function":{"name":"get_b","arguments":"{\"b\":2}"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#,
+                    "[DONE]",
+                ],
+                vec![
+                    start("chatcmpl-1"),
+                    tool_use(0, "call_a", "get_a"),
+                    input_json(0, r#"{"a":1}"#),
+                    EventData::ContentBlockStop { index: 0 },
+                    tool_use(1, "call_b", "get_b"),
+                    input_json(1, r#"{"b":2}"#),
+                    EventData::ContentBlockStop { index: 1 },
+                    EventData::MessageDelta {
+                        delta: MessageDelta {
+                            stop_reason: StopReason::ToolUse,
+                            stop_sequence: None,
+                        },
+                        usage: zero_usage.clone(),
+                    },
+                    EventData::MessageStop,
+                ],
+            ),
+            (
+                "arguments after the block closed",
+                vec![
+                    r#"{"id":"chatcmpl-2","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","tool_calls":[{"index":0,"id":"call_a","type":"function","function":{"name":"get_a","arguments":"{\"a\":"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-2","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":"Hi"}}]}"#,
+                    r#"{"id":"chatcmpl-2","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"1}"}}]}}]}"#,
+                    r#"{"id":"chatcmpl-2","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#,
+                    "[DONE]",
+                ],
+                vec![
+                    start("chatcmpl-2"),
+                    tool_use(0, "call_a", "get_a"),
+                    input_json(0, r#"{"a":"#),
+                    EventData::ContentBlockStop { index: 0 },
+                    EventData::ContentBlockStart {
+                        index: 1,
+                        content_block: ResponseContentBlock::Base(BaseContentBlock::Text {
+                            text: String::new(),
+                            cache_control: None,
+                        }),
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 1,
+                        delta: DeltaContentBlock::TextDelta {
+                            text: "Hi".to_string(),
+                        },
+                    },
+                    EventData::ContentBlockStop { index: 1 },
+                    EventData::Error {
+                        error: ErrorData::ApiError {
+                            message: "arguments of tool call 0 arrived after its block was closed"
+                                .to_string(),
+                        },
+                    },
+                ],
+            ),
+            (
+                "refusal",
+                vec![
+                    r#"{"id":"chatcmpl-3","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","refusal":"I can't"}}]}"#,
+                    r#"{"id":"chatcmpl-3","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"refusal":" help."}}]}"#,
+                    r#"{"id":"chatcmpl-3","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{},"finish_reason":"content_filter"}]}"#,
+                    "[DONE]",
+                ],
+                vec![
+                    start("chatcmpl-3"),
+                    EventData::ContentBlockStart {
+                        index: 0,
+                        content_block: ResponseContentBlock::Base(BaseContentBlock::Text {
+                            text: String::new(),
+                            cache_control: None,
+                        }),
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 0,
+                        delta: DeltaContentBlock::TextDelta {
+                            text: "I can't".to_string(),
+                        },
+                    },
+                    EventData::ContentBlockDelta {
+                        index: 0,
+                        delta: DeltaContentBlock::TextDelta {
+                            text: " help.".to_string(),
+                        },
+                    },
+                    EventData::ContentBlockStop { index: 0 },
+                    EventData::MessageDelta {
+                        delta: MessageDelta {
+                            stop_reason: StopReason::Refusal,
+                            stop_sequence: None,
+                        },
+                        usage: zero_usage.clone(),
+                    },
+                    EventData::MessageStop,
+                ],
+            ),
+        ];
+        for (name, chunks, want) in tests {
+            let mut encoder = OpenaiToClaudeEventEncoder::default();
+            let mut got = vec![];
+            for chunk in chunks {
+                let chunk: Chunk = chunk.parse().unwrap();
+                got.extend(encoder.encode(&chunk));
+            }
+            assert_eq!(got, want, "encode test failed: {}", name);
+        }
+    }
+}
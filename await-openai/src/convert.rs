@@ -0,0 +1,192 @@
+use std::fmt;
+
+use crate::entity::create_chat_completion::RequestBody;
+
+/// What happened to a field or part the target provider can't express.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LossKind {
+    /// Removed from the converted request.
+    Dropped,
+    /// Kept, but in a less faithful form.
+    Degraded,
+}
+
+/// A single field or content part that didn't survive the conversion unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionIssue {
+    pub kind: LossKind,
+    /// Index into the openai `messages`, `None` for request level fields.
+    pub message_index: Option<usize>,
+    /// Index into the content parts of the message.
+    pub part_index: Option<usize>,
+    /// The openai field or part type, e.g. `logit_bias` or `image_url`.
+    pub field: &'static str,
+    pub reason: String,
+}
+
+impl ConversionIssue {
+    pub fn dropped(field: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            kind: LossKind::Dropped,
+            message_index: None,
+            part_index: None,
+            field,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn degraded(field: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            kind: LossKind::Degraded,
+            ..Self::dropped(field, reason)
+        }
+    }
+
+    pub fn at_message(mut self, index: usize) -> Self {
+        self.message_index = Some(index);
+        self
+    }
+
+    pub fn at_part(mut self, index: usize) -> Self {
+        self.part_index = Some(index);
+        self
+    }
+}
+
+impl fmt::Display for ConversionIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(message_index) = self.message_index {
+            write!(f, "messages[{}]", message_index)?;
+            if let Some(part_index) = self.part_index {
+                write!(f, ".content[{}]", part_index)?;
+            }
+            write!(f, ".")?;
+        }
+        let kind = match self.kind {
+            LossKind::Dropped => "dropped",
+            LossKind::Degraded => "degraded",
+        };
+        write!(f, "{} {}: {}", self.field, kind, self.reason)
+    }
+}
+
+/// Everything a conversion dropped or degraded, in the order it was found.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversionReport {
+    pub issues: Vec<ConversionIssue>,
+}
+
+impl ConversionReport {
+    pub fn is_lossless(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConversionMode {
+    /// Drop or degrade what can't be expressed and record it in the report.
+    #[default]
+    Lossy,
+    /// Fail on the first thing that can't be expressed.
+    Strict,
+}
+
+/// Returned by a strict conversion, carries the first issue found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionError(pub ConversionIssue);
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "lossy conversion: {}", self.0)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// A converted request together with its report, returned by the provider's `convert_with_report`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Converted<T> {
+    pub request: T,
+    pub report: ConversionReport,
+}
+
+pub(crate) struct Reporter {
+    mode: ConversionMode,
+    report: ConversionReport,
+}
+
+impl Reporter {
+    pub(crate) fn new(mode: ConversionMode) -> Self {
+        Self {
+            mode,
+            report: ConversionReport::default(),
+        }
+    }
+
+    pub(crate) fn report(&mut self, issue: ConversionIssue) -> Result<(), ConversionError> {
+        match self.mode {
+            ConversionMode::Strict => Err(ConversionError(issue)),
+            ConversionMode::Lossy => {
+                self.report.issues.push(issue);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn finish<T>(self, request: T) -> Converted<T> {
+        Converted {
+            request,
+            report: self.report,
+        }
+    }
+}
+
+/// `max_completion_tokens` falls back to the deprecated `max_tokens`, a `max_tokens` that disagrees with it is reported.
+pub(crate) fn max_completion_tokens(
+    body: &RequestBody,
+    reporter: &mut Reporter,
+) -> Result<Option<u32>, ConversionError> {
+    #[allow(deprecated)]
+    let max_tokens = body.max_tokens;
+    if let (Some(max_completion_tokens), Some(max_tokens)) =
+        (body.max_completion_tokens, max_tokens)
+        && max_completion_tokens != max_tokens
+    {
+        reporter.report(ConversionIssue::dropped(
+            "max_tokens",
+            "conflicts with max_completion_tokens, which is used",
+        ))?;
+    }
+    Ok(body.max_completion_tokens.or(max_tokens))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let tests = vec![
+            (
+                "request level",
+                ConversionIssue::dropped("seed", "not supported"),
+                "seed dropped: not supported",
+            ),
+            (
+                "message level",
+                ConversionIssue::degraded("name", "merged into content").at_message(2),
+                "messages[2].name degraded: merged into content",
+            ),
+            (
+                "part level",
+                ConversionIssue::dropped("image_url", "only base64 images are supported")
+                    .at_message(1)
+                    .at_part(0),
+                "messages[1].content[0].image_url dropped: only base64 images are supported",
+            ),
+        ];
+        for (name, issue, want) in tests {
+            assert_eq!(issue.to_string(), want, "display test failed: {}", name);
+        }
+    }
+}
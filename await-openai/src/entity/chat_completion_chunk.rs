@@ -1,13 +1,22 @@
-use std::{collections::VecDeque, convert::Infallible, str::FromStr};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    convert::Infallible,
+    fmt,
+    str::FromStr,
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::magi::EventDataParser;
+use crate::{
+    magi::EventDataParser,
+    partial_json::{self, PartialJson},
+};
 
 use super::{
     chat_completion_object::{
-        Annotation, ChatCompletionAudio, Choice as ChatCompletionChoice, Logprobs, Message,
-        Response as ChatCompletionResponse, Role, ServiceTier, Usage,
+        Annotation, ChatCompletionAudio, Choice as ChatCompletionChoice, Err as ApiErr,
+        ErrResponse, LogprobContent, Logprobs, Message, Response as ChatCompletionResponse, Role,
+        ServiceTier, Usage,
     },
     create_chat_completion::{FinishReason, ToolCall, ToolCallFunction, ToolCallFunctionObj},
 };
@@ -18,16 +27,51 @@ pub enum Chunk {
     Data(ChunkResponse),
 }
 
+/// Returned by `Chunk::from_str`.
+#[derive(Debug)]
+pub enum ChunkError {
+    Json(serde_json::Error),
+    /// The server sent `{"error": {...}}` in place of a chunk, as OpenRouter and vLLM do mid-stream.
+    Api(ApiErr),
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkError::Json(e) => write!(f, "{}", e),
+            ChunkError::Api(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChunkError::Json(e) => Some(e),
+            ChunkError::Api(e) => Some(e),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ChunkError {
+    fn from(e: serde_json::Error) -> Self {
+        ChunkError::Json(e)
+    }
+}
+
 impl FromStr for Chunk {
-    type Err = serde_json::Error;
+    type Err = ChunkError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "[DONE]" => Ok(Chunk::Done),
-            _ => {
-                let response = serde_json::from_str::<ChunkResponse>(s)?;
-                Ok(Chunk::Data(response))
-            }
+            _ => match serde_json::from_str::<ChunkResponse>(s) {
+                Ok(response) => Ok(Chunk::Data(response)),
+                Err(e) => match serde_json::from_str::<ErrResponse>(s) {
+                    Ok(response) => Err(ChunkError::Api(response.error)),
+                    Err(_) => Err(ChunkError::Json(e)),
+                },
+            },
         }
     }
 }
@@ -139,93 +183,88 @@ pub struct ToolCallFunctionObjChunk {
     pub arguments: String,
 }
 
+/// A tool call whose arguments are still being streamed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialToolCall {
+    pub id: String,
+    pub name: String,
+    /// The arguments received so far, `None` if they aren't valid JSON.
+    pub arguments: Option<PartialJson>,
+}
+
+impl PartialToolCall {
+    pub fn new(id: impl Into<String>, name: impl Into<String>, arguments: &str) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            arguments: partial_json::parse(arguments),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct OpenaiEventDataParser {
     pub id: String,
-    tool_call_chunk: Option<ToolCallChunk>,
     pub object: String,
     pub created: u64,
     pub model: String,
     system_fingerprint: Option<String>,
+    /// Sent in the last chunk when `stream_options.include_usage` is set.
+    usage: Option<Usage>,
+    /// The state of each choice, keyed by `Choice::index`.
+    choices: BTreeMap<usize, ChoiceState>,
+}
+
+/// The aggregated state of a single choice.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct ChoiceState {
+    /// Tool calls still being streamed, keyed by `ToolCallChunk::index`.
+    tool_call_chunks: BTreeMap<usize, ToolCallChunk>,
+    /// Tool calls already emitted, keyed by `ToolCallChunk::index`, pointing into `tool_calls`.
+    emitted_tool_calls: BTreeMap<usize, usize>,
     tool_calls: VecDeque<ToolCall>,
-    pub content: String,
+    content: String,
+    think_content: String,
     refusal: Option<String>,
     annotations: Option<Vec<Annotation>>,
     audio: Option<ChatCompletionAudio>,
     logprobs: Option<Logprobs>,
     finish_reason: Option<FinishReason>,
-    pub think_content: String,
 }
 
 impl EventDataParser<Chunk> for OpenaiEventDataParser {
     type Error = Infallible;
-    type Output = Option<ToolCall>;
+    /// The tool calls completed by the chunk.
+    type Output = Vec<ToolCall>;
     type UnarayResponse = ChatCompletionResponse;
 
-    fn parse(&mut self, data: &Chunk) -> Result<Option<ToolCall>, Infallible> {
+    fn parse(&mut self, data: &Chunk) -> Result<Vec<ToolCall>, Infallible> {
+        let mut completed = vec![];
         match data {
             Chunk::Data(response) => {
                 self.update_basic_info(response);
-                if let Some(choice) = response.choices.first() {
-                    self.logprobs.clone_from(&choice.logprobs);
-
-                    if let Some(reason) = choice.finish_reason {
-                        self.finish_reason = Some(reason);
-                    }
-
-                    //simple content
-                    if let Some(c) = choice.delta.content.as_ref() {
-                        self.content.push_str(c);
-                    }
-                    if let Some(c) = choice.delta.reasoning.as_ref() {
-                        self.think_content.push_str(c);
-                    }
-
-                    if let Some(refusal) = choice.delta.refusal.as_ref() {
-                        self.refusal = Some(refusal.clone());
-                    }
-
-                    if let Some(annotations) = choice.delta.annotations.as_ref() {
-                        self.annotations = Some(annotations.clone());
-                    }
-
-                    if let Some(audio) = choice.delta.audio.as_ref() {
-                        self.audio = Some(audio.clone());
-                    }
-
-                    if let Some(tool_calls) = choice.delta.tool_calls.as_ref() {
-                        return Ok(self.parse_tool_call_chunk(tool_calls.first()));
-                    }
-                    return Ok(self.parse_tool_call_chunk(None));
+                if let Some(usage) = response.usage.as_ref() {
+                    self.usage = Some(usage.clone());
+                }
+                for choice in response.choices.iter() {
+                    completed.extend(self.choice_mut(choice.index).parse(choice));
+                }
+            }
+            Chunk::Done => {
+                for state in self.choices.values_mut() {
+                    completed.extend(state.flush_tool_calls());
                 }
-                Ok(None)
             }
-            Chunk::Done => Ok(None),
         }
+        Ok(completed)
     }
 
-    fn response(self) -> ChatCompletionResponse {
-        let mut m = Message {
-            role: Role::Assistant,
-            ..Default::default()
-        };
-        if !self.think_content.is_empty() {
-            m.reasoning = Some(self.think_content);
-        }
-        if !self.content.is_empty() {
-            m.content = Some(self.content);
-        }
-        if let Some(refusal) = self.refusal {
-            m.refusal = Some(refusal);
+    fn response(mut self) -> ChatCompletionResponse {
+        for state in self.choices.values_mut() {
+            state.flush_tool_calls();
         }
-        if let Some(annotations) = self.annotations {
-            m.annotations = Some(annotations);
-        }
-        if let Some(audio) = self.audio {
-            m.audio = Some(audio);
-        }
-        if !self.tool_calls.is_empty() {
-            m.tool_calls = Some(self.tool_calls.into());
+        if self.choices.is_empty() {
+            self.choices.insert(0, ChoiceState::default());
         }
         ChatCompletionResponse {
             id: self.id,
@@ -234,15 +273,12 @@ impl EventDataParser<Chunk> for OpenaiEventDataParser {
             model: self.model,
             system_fingerprint: self.system_fingerprint.clone(),
             service_tier: None,
-            choices: vec![
-                ChatCompletionChoice {
-                    index: 0,
-                    message: m,
-                    finish_reason: self.finish_reason,
-                    logprobs: self.logprobs,
-                },
-            ],
-            usage: Usage::default(),
+            choices: self
+                .choices
+                .into_iter()
+                .map(|(index, state)| state.into_choice(index))
+                .collect(),
+            usage: self.usage.unwrap_or_default(),
         }
     }
 }
@@ -266,25 +302,70 @@ impl OpenaiEventDataParser {
         self.system_fingerprint = system_fingerprint;
     }
 
+    /// Set the finish reason of the first choice.
     pub fn set_finish_reason(&mut self, finish_reason: Option<FinishReason>) {
-        self.finish_reason = finish_reason;
+        self.choice_mut(0).finish_reason = finish_reason;
     }
 
-    pub fn parse_str(&mut self, data: &str) -> Result<Option<ToolCall>, serde_json::Error> {
+    pub fn parse_str(&mut self, data: &str) -> Result<Vec<ToolCall>, ChunkError> {
         let chunk = Chunk::from_str(data)?;
         Ok(self.parse(&chunk).unwrap())
     }
 
+    /// The usage of the stream, `None` until the usage chunk arrives.
+    pub fn usage(&self) -> Option<&Usage> {
+        self.usage.as_ref()
+    }
+
+    /// The content of the first choice aggregated so far.
+    pub fn content(&self) -> &str {
+        self.choices
+            .get(&0)
+            .map(|state| state.content.as_str())
+            .unwrap_or_default()
+    }
+
+    /// The reasoning content of the first choice aggregated so far.
+    pub fn think_content(&self) -> &str {
+        self.choices
+            .get(&0)
+            .map(|state| state.think_content.as_str())
+            .unwrap_or_default()
+    }
+
+    /// The tool calls of the first choice still being streamed, in index order.
+    pub fn partial_tool_calls(&self) -> Vec<PartialToolCall> {
+        self.choices
+            .get(&0)
+            .map(|state| {
+                state
+                    .tool_call_chunks
+                    .values()
+                    .map(|chunk| {
+                        PartialToolCall::new(
+                            chunk.id.clone().unwrap_or_default(),
+                            chunk.function.name.clone().unwrap_or_default(),
+                            &chunk.function.arguments,
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Append to the content of the first choice.
     pub fn push_content(&mut self, content: &str) {
-        self.content.push_str(content);
+        self.choice_mut(0).content.push_str(content);
     }
 
+    /// Append to the reasoning content of the first choice.
     pub fn push_thinking(&mut self, content: &str) {
-        self.think_content.push_str(content);
+        self.choice_mut(0).think_content.push_str(content);
     }
 
+    /// Add a complete tool call to the first choice.
     pub fn push_tool_call(&mut self, tool_call: ToolCall) {
-        self.tool_calls.push_back(tool_call);
+        self.choice_mut(0).tool_calls.push_back(tool_call);
     }
 }
 
@@ -299,59 +380,328 @@ impl OpenaiEventDataParser {
         self.system_fingerprint = response.system_fingerprint.clone();
     }
 
-    fn parse_tool_call_chunk(
-        &mut self,
-        tool_call_chunk: Option<&ToolCallChunk>,
-    ) -> Option<ToolCall> {
-        match (self.tool_call_chunk.take(), tool_call_chunk) {
-            (Some(mut prev_tool_call), Some(new_tool_call)) => {
-                // If we see a new tool call with different id
-                // return the prev tool call and start fresh with the new one
-                if new_tool_call.id.is_some() && prev_tool_call.id != new_tool_call.id {
-                    let tool_call = ToolCall::Function(ToolCallFunction {
-                        id: prev_tool_call.id.unwrap_or_default(),
-                        function: ToolCallFunctionObj {
-                            name: prev_tool_call.function.name.unwrap_or_default(),
-                            arguments: prev_tool_call.function.arguments.clone(),
-                        },
-                    });
-                    self.tool_calls.push_back(tool_call.clone());
-                    // Start fresh with the new tool call exactly as received
-                    self.tool_call_chunk = Some(new_tool_call.clone());
+    fn choice_mut(&mut self, index: usize) -> &mut ChoiceState {
+        self.choices.entry(index).or_default()
+    }
+}
 
-                    return Some(tool_call);
-                }
-                // Continue building the same tool call by appending arguments
-                prev_tool_call
-                    .function
-                    .arguments
-                    .push_str(new_tool_call.function.arguments.as_str());
-                self.tool_call_chunk = Some(prev_tool_call);
-                None
+impl ChoiceState {
+    fn parse(&mut self, choice: &Choice) -> Vec<ToolCall> {
+        // each chunk carries the logprobs of its own tokens
+        if let Some(logprobs) = choice.logprobs.as_ref() {
+            let aggregated = self.logprobs.get_or_insert_with(Logprobs::default);
+            append_logprobs(&mut aggregated.content, logprobs.content.as_deref());
+            append_logprobs(&mut aggregated.refusal, logprobs.refusal.as_deref());
+        }
+
+        if let Some(reason) = choice.finish_reason {
+            self.finish_reason = Some(reason);
+        }
+
+        //simple content
+        if let Some(c) = choice.delta.content.as_ref() {
+            self.content.push_str(c);
+        }
+        if let Some(c) = choice.delta.reasoning.as_ref() {
+            self.think_content.push_str(c);
+        }
+
+        if let Some(refusal) = choice.delta.refusal.as_ref() {
+            self.refusal
+                .get_or_insert_with(String::new)
+                .push_str(refusal);
+        }
+
+        if let Some(annotations) = choice.delta.annotations.as_ref() {
+            self.annotations = Some(annotations.clone());
+        }
+
+        if let Some(audio) = choice.delta.audio.as_ref() {
+            self.audio = Some(audio.clone());
+        }
+
+        let mut completed = vec![];
+        for tool_call_chunk in choice.delta.tool_calls.iter().flatten() {
+            completed.extend(self.parse_tool_call_chunk(tool_call_chunk));
+        }
+        if choice.finish_reason.is_some() {
+            completed.extend(self.flush_tool_calls());
+        }
+        completed
+    }
+
+    fn into_choice(self, index: usize) -> ChatCompletionChoice {
+        let mut m = Message {
+            role: Role::Assistant,
+            ..Default::default()
+        };
+        if !self.think_content.is_empty() {
+            m.reasoning = Some(self.think_content);
+        }
+        if !self.content.is_empty() {
+            m.content = Some(self.content);
+        }
+        if let Some(refusal) = self.refusal {
+            m.refusal = Some(refusal);
+        }
+        if let Some(annotations) = self.annotations {
+            m.annotations = Some(annotations);
+        }
+        if let Some(audio) = self.audio {
+            m.audio = Some(audio);
+        }
+        if !self.tool_calls.is_empty() {
+            m.tool_calls = Some(self.tool_calls.into());
+        }
+        ChatCompletionChoice {
+            index,
+            message: m,
+            finish_reason: self.finish_reason,
+            logprobs: self.logprobs,
+        }
+    }
+
+    fn parse_tool_call_chunk(&mut self, tool_call_chunk: &ToolCallChunk) -> Vec<ToolCall> {
+        let mut completed = vec![];
+        if let Some(prev) = self.tool_call_chunks.get_mut(&tool_call_chunk.index) {
+            // Some providers reuse the index, a new id starts a new tool call
+            if tool_call_chunk.id.is_some() && prev.id != tool_call_chunk.id {
+                let prev = std::mem::replace(prev, tool_call_chunk.clone());
+                completed.push(self.complete_tool_call(prev));
+                return completed;
             }
-            (None, Some(tool)) => {
-                //set the new tool call
-                self.tool_call_chunk = Some(tool.clone());
-                None
+            prev.function
+                .arguments
+                .push_str(&tool_call_chunk.function.arguments);
+            if prev.id.is_none() {
+                prev.id.clone_from(&tool_call_chunk.id);
             }
-            (Some(prev_tool_call), None) => {
-                let tool_call = ToolCall::Function(ToolCallFunction {
-                    id: prev_tool_call.id.unwrap_or_default(),
-                    function: ToolCallFunctionObj {
-                        name: prev_tool_call.function.name.unwrap_or_default(),
-                        arguments: prev_tool_call.function.arguments.clone(),
-                    },
+            if prev.function.name.is_none() {
+                prev.function
+                    .name
+                    .clone_from(&tool_call_chunk.function.name);
+            }
+            return completed;
+        }
+
+        // A late fragment of a tool call already emitted, keep it in the aggregated response
+        if tool_call_chunk.id.is_none()
+            && let Some(&position) = self.emitted_tool_calls.get(&tool_call_chunk.index)
+            && let Some(ToolCall::Function(tool_call)) = self.tool_calls.get_mut(position)
+        {
+            tool_call
+                .function
+                .arguments
+                .push_str(&tool_call_chunk.function.arguments);
+            return completed;
+        }
+
+        // A new index starts, the arguments of the other tool calls may still be interleaved,
+        // only those with complete arguments are done.
+        let done: Vec<usize> = self
+            .tool_call_chunks
+            .iter()
+            .filter(|(_, chunk)| {
+                serde_json::from_str::<serde::de::IgnoredAny>(&chunk.function.arguments).is_ok()
+            })
+            .map(|(index, _)| *index)
+            .collect();
+        for index in done {
+            if let Some(chunk) = self.tool_call_chunks.remove(&index) {
+                completed.push(self.complete_tool_call(chunk));
+            }
+        }
+        self.tool_call_chunks
+            .insert(tool_call_chunk.index, tool_call_chunk.clone());
+        completed
+    }
+
+    /// Complete every tool call still being streamed, in index order.
+    fn flush_tool_calls(&mut self) -> Vec<ToolCall> {
+        std::mem::take(&mut self.tool_call_chunks)
+            .into_values()
+            .map(|chunk| self.complete_tool_call(chunk))
+            .collect()
+    }
+
+    fn complete_tool_call(&mut self, chunk: ToolCallChunk) -> ToolCall {
+        self.emitted_tool_calls
+            .insert(chunk.index, self.tool_calls.len());
+        let tool_call = ToolCall::Function(ToolCallFunction {
+            id: chunk.id.unwrap_or_default(),
+            function: ToolCallFunctionObj {
+                name: chunk.function.name.unwrap_or_default(),
+                arguments: chunk.function.arguments,
+            },
+        });
+        self.tool_calls.push_back(tool_call.clone());
+        tool_call
+    }
+}
+
+fn append_logprobs(
+    aggregated: &mut Option<Vec<LogprobContent>>,
+    logprobs: Option<&[LogprobContent]>,
+) {
+    if let Some(logprobs) = logprobs {
+        aggregated
+            .get_or_insert_with(Vec::new)
+            .extend_from_slice(logprobs);
+    }
+}
+
+/// How [`ChatCompletionResponse::to_chunks`] splits a response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkOptions {
+    /// The number of characters of content, reasoning or tool call arguments per chunk, `0` sends each in one chunk.
+    pub chunk_size: usize,
+    /// Send the usage in a chunk with no choices, as `stream_options.include_usage` does.
+    pub include_usage: bool,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 16,
+            include_usage: false,
+        }
+    }
+}
+
+impl ChatCompletionResponse {
+    /// The chunks a streaming call would have returned for this response, ending with `Chunk::Done`.
+    ///
+    /// For each choice: the role, the reasoning, the content, the refusal, annotations and audio,
+    /// the tool calls with their arguments, then the finish reason with the logprobs.
+    pub fn to_chunks(&self, options: &ChunkOptions) -> Vec<Chunk> {
+        let mut chunks = vec![];
+        for choice in self.choices.iter() {
+            let mut deltas = vec![
+                DeltaMessage {
+                    role: Some(Role::Assistant),
+                    ..Default::default()
+                },
+            ];
+            let message = &choice.message;
+            for piece in split_pieces(message.reasoning.as_deref(), options.chunk_size) {
+                deltas.push(DeltaMessage {
+                    reasoning: Some(piece.to_string()),
+                    ..Default::default()
+                });
+            }
+            for piece in split_pieces(message.content.as_deref(), options.chunk_size) {
+                deltas.push(DeltaMessage {
+                    content: Some(piece.to_string()),
+                    ..Default::default()
+                });
+            }
+            for piece in split_pieces(message.refusal.as_deref(), options.chunk_size) {
+                deltas.push(DeltaMessage {
+                    refusal: Some(piece.to_string()),
+                    ..Default::default()
+                });
+            }
+            if message.annotations.is_some() || message.audio.is_some() {
+                deltas.push(DeltaMessage {
+                    annotations: message.annotations.clone(),
+                    audio: message.audio.clone(),
+                    ..Default::default()
                 });
-                self.tool_calls.push_back(tool_call.clone());
-                Some(tool_call)
             }
-            (None, None) => None,
+            for (index, tool_call) in message.tool_calls.iter().flatten().enumerate() {
+                let ToolCall::Function(call) = tool_call;
+                deltas.push(DeltaMessage {
+                    tool_calls: Some(vec![
+                        ToolCallChunk {
+                            index,
+                            id: Some(call.id.clone()),
+                            r#type: Some("function".to_string()),
+                            function: ToolCallFunctionObjChunk {
+                                name: Some(call.function.name.clone()),
+                                arguments: String::new(),
+                            },
+                        },
+                    ]),
+                    ..Default::default()
+                });
+                for piece in split_pieces(Some(&call.function.arguments), options.chunk_size) {
+                    deltas.push(DeltaMessage {
+                        tool_calls: Some(vec![
+                            ToolCallChunk {
+                                index,
+                                function: ToolCallFunctionObjChunk {
+                                    name: None,
+                                    arguments: piece.to_string(),
+                                },
+                                ..Default::default()
+                            },
+                        ]),
+                        ..Default::default()
+                    });
+                }
+            }
+
+            for delta in deltas {
+                chunks.push(Chunk::Data(self.chunk_response(vec![Choice {
+                    index: choice.index,
+                    delta,
+                    ..Default::default()
+                }])));
+            }
+            chunks.push(Chunk::Data(self.chunk_response(vec![Choice {
+                index: choice.index,
+                delta: DeltaMessage::default(),
+                finish_reason: choice.finish_reason,
+                logprobs: choice.logprobs.clone(),
+            }])));
+        }
+        if options.include_usage {
+            let mut usage = self.chunk_response(vec![]);
+            usage.usage = Some(self.usage.clone());
+            chunks.push(Chunk::Data(usage));
+        }
+        chunks.push(Chunk::Done);
+        chunks
+    }
+
+    fn chunk_response(&self, choices: Vec<Choice>) -> ChunkResponse {
+        ChunkResponse {
+            id: self.id.clone(),
+            choices,
+            created: self.created,
+            model: self.model.clone(),
+            system_fingerprint: self.system_fingerprint.clone(),
+            object: "chat.completion.chunk".to_string(),
+            service_tier: self.service_tier.clone(),
+            usage: None,
         }
     }
 }
 
+/// Split `s` into pieces of `size` characters.
+fn split_pieces(s: Option<&str>, size: usize) -> Vec<&str> {
+    let Some(s) = s.filter(|s| !s.is_empty()) else {
+        return vec![];
+    };
+    if size == 0 {
+        return vec![s];
+    }
+    let mut pieces = vec![];
+    let mut start = 0;
+    for (count, (i, _)) in s.char_indices().enumerate() {
+        if count > 0 && count % size == 0 {
+            pieces.push(&s[start..i]);
+            start = i;
+        }
+    }
+    pieces.push(&s[start..]);
+    pieces
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::entity::chat_completion_object::{CompletionTokensDetails, PromptTokensDetails};
+
     use super::*;
 
     #[test]
@@ -483,127 +833,132 @@ mod tests {
             (
                 "start",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"role":"assistant","content":null},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data1",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_UjeNA45J26mfwbeEXi3AfNL1","type":"function","function":{"name":"get_current_weather","arguments":""}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data2",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\""}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data3",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"locatio"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data4",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"n\": \"N"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data5",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"ew Y"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data6",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"ork\","}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data7",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":" \"unit"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data8",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\": \""}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data9",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"celsi"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data10",
                 r#"{"nonce": "3cc0e9", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"us\"}"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data11",
                 r#"{"nonce": "1684c1", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"id":"call_7e8O5F7pyvxpqYLPiiIL2FMH","type":"function","function":{"name":"get_current_weather","arguments":""}}]},"logprobs":null,"finish_reason":null}]}"#,
-                Some(ToolCall::Function(ToolCallFunction {
-                    id: "call_UjeNA45J26mfwbeEXi3AfNL1".to_string(),
-                    function: ToolCallFunctionObj {
-                        name: "get_current_weather".to_string(),
-                        arguments: "{\"location\": \"New York\", \"unit\": \"celsius\"}"
-                            .to_string(),
-                    },
-                })),
+                vec![
+                    ToolCall::Function(ToolCallFunction {
+                        id: "call_UjeNA45J26mfwbeEXi3AfNL1".to_string(),
+                        function: ToolCallFunctionObj {
+                            name: "get_current_weather".to_string(),
+                            arguments: "{\"location\": \"New York\", \"unit\": \"celsius\"}"
+                                .to_string(),
+                        },
+                    }),
+                ],
             ),
             (
                 "data12",
                 r#"{"nonce": "1684c1", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"{\"lo"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data13",
                 r#"{"nonce": "1684c1", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"catio"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data14",
                 r#"{"nonce": "1684c1", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"n\": \"T"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data15",
                 r#"{"nonce": "1684c1", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"okyo"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data16",
                 r#"{"nonce": "1684c1", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"\", \"u"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data17",
                 r#"{"nonce": "1684c1", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"nit\": "}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data18",
                 r#"{"nonce": "1684c1", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"\"cel"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data19",
                 r#"{"nonce": "1684c1", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"sius\""}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data20",
                 r#"{"nonce": "1684c1", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"}"}}]},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data21",
                 r#"{"nonce": "dd25883214", "id":"chatcmpl-941BLfWSKMsoPyCbL3UpYjvQG3oTU","object":"chat.completion.chunk","created":1710744883,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{},"logprobs":null,"finish_reason":"tool_calls"}]}"#,
-                Some(ToolCall::Function(ToolCallFunction {
-                    id: "call_7e8O5F7pyvxpqYLPiiIL2FMH".to_string(),
-                    function: ToolCallFunctionObj {
-                        name: "get_current_weather".to_string(),
-                        arguments: "{\"location\": \"Tokyo\", \"unit\": \"celsius\"}".to_string(),
-                    },
-                })),
+                vec![
+                    ToolCall::Function(ToolCallFunction {
+                        id: "call_7e8O5F7pyvxpqYLPiiIL2FMH".to_string(),
+                        function: ToolCallFunctionObj {
+                            name: "get_current_weather".to_string(),
+                            arguments: "{\"location\": \"Tokyo\", \"unit\": \"celsius\"}"
+                                .to_string(),
+                        },
+                    }),
+                ],
             ),
-            ("Done", "[DONE]", None),
+            ("Done", "[DONE]", vec![]),
         ];
         let mut parser = OpenaiEventDataParser::default();
         for (name, data, want) in test_cases {
@@ -672,19 +1027,19 @@ mod tests {
             (
                 "start",
                 r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-3.5-turbo-0613", "system_fingerprint": "fp_44709d6fcb", "choices":[{"index":0,"delta":{"role":"assistant","content":""},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-3.5-turbo-0613", "system_fingerprint": "fp_44709d6fcb", "choices":[{"index":0,"delta":{"content":"Hello"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "last_chunk_with_content_and_finish",
                 r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-3.5-turbo-0613", "system_fingerprint": "fp_44709d6fcb", "choices":[{"index":0,"delta":{"content":" world!"},"logprobs":null,"finish_reason":"stop"}]}"#,
-                None,
+                vec![],
             ),
-            ("Done", "[DONE]", None),
+            ("Done", "[DONE]", vec![]),
         ];
 
         let mut parser = OpenaiEventDataParser::default();
@@ -735,189 +1090,189 @@ mod tests {
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"role":"assistant","content":""},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":"Hello"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":"!"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" I"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":"'m"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" just"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" a"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" computer"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" program"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":","},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" so"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" I"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" don"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":"'t"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" have"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" feelings"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":","},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" but"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" I"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":"'m"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" ready"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" to"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" assist"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" you"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" with"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" anything"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" you"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" need"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":"."},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" How"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" can"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" I"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" help"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" you"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":" today"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{"content":"?"},"logprobs":null,"finish_reason":null}]}"#,
-                None,
+                vec![],
             ),
             (
                 "data",
                 r#"{"id":"chatcmpl-94JCcQJ9TY5hHx1el8uXAzojc511L","object":"chat.completion.chunk","created":1710814154,"model":"gpt-3.5-turbo-0125","system_fingerprint":"fp_4f2ebda25a","choices":[{"index":0,"delta":{},"logprobs":null,"finish_reason":"stop"}]}"#,
-                None,
+                vec![],
             ),
-            ("data", r#"[DONE]"#, None),
+            ("data", r#"[DONE]"#, vec![]),
         ];
         let mut parser = OpenaiEventDataParser::default();
         for (name, data, want) in test_cases {
@@ -955,4 +1310,518 @@ mod tests {
         };
         assert_eq!(res, want_res, "get_response failed")
     }
+
+    #[test]
+    fn test_parser_for_multiple_choices() {
+        let test_cases = vec![
+            (
+                "start",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":1,"delta":{"role":"assistant","content":""},"finish_reason":null},{"index":0,"delta":{"role":"assistant","content":""},"finish_reason":null}]}"#,
+                vec![],
+            ),
+            (
+                "choice 0",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":"Hello"},"finish_reason":null}]}"#,
+                vec![],
+            ),
+            (
+                "choice 1",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":1,"delta":{"content":"Hi"},"finish_reason":null}]}"#,
+                vec![],
+            ),
+            (
+                "choice 1 tool call",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":1,"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"greet","arguments":"{}"}}]},"finish_reason":null}]}"#,
+                vec![],
+            ),
+            (
+                "both finish",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":" there"},"finish_reason":"stop"},{"index":1,"delta":{},"finish_reason":"tool_calls"}]}"#,
+                vec![
+                    ToolCall::Function(ToolCallFunction {
+                        id: "call_1".to_string(),
+                        function: ToolCallFunctionObj {
+                            name: "greet".to_string(),
+                            arguments: "{}".to_string(),
+                        },
+                    }),
+                ],
+            ),
+            ("done", "[DONE]", vec![]),
+        ];
+        let mut parser = OpenaiEventDataParser::default();
+        for (name, data, want) in test_cases {
+            let got = parser.parse_str(data).unwrap();
+            assert_eq!(got, want, "test_parser failed: {}", name);
+        }
+        assert_eq!(parser.content(), "Hello there");
+        let res = parser.response();
+        assert_eq!(
+            res.choices,
+            vec![
+                ChatCompletionChoice {
+                    index: 0,
+                    message: Message {
+                        role: Role::Assistant,
+                        content: Some("Hello there".to_string()),
+                        ..Default::default()
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    logprobs: None,
+                },
+                ChatCompletionChoice {
+                    index: 1,
+                    message: Message {
+                        role: Role::Assistant,
+                        content: Some("Hi".to_string()),
+                        tool_calls: Some(vec![
+                            ToolCall::Function(ToolCallFunction {
+                                id: "call_1".to_string(),
+                                function: ToolCallFunctionObj {
+                                    name: "greet".to_string(),
+                                    arguments: "{}".to_string(),
+                                },
+                            })
+                        ]),
+                        ..Default::default()
+                    },
+                    finish_reason: Some(FinishReason::ToolCalls),
+                    logprobs: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_for_refusal() {
+        let chunks = vec![
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","refusal":""},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"refusal":"I'm sorry, "},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"refusal":"I can't help "},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"refusal":"with that."},"finish_reason":"stop"}]}"#,
+            "[DONE]",
+        ];
+        let mut parser = OpenaiEventDataParser::default();
+        for chunk in chunks {
+            parser.parse_str(chunk).unwrap();
+        }
+        let res = parser.response();
+        assert_eq!(
+            res.choices[0].message.refusal.as_deref(),
+            Some("I'm sorry, I can't help with that.")
+        );
+        assert_eq!(res.choices[0].message.content, None);
+    }
+
+    #[test]
+    fn test_parser_for_partial_tool_calls() {
+        let test_cases = vec![
+            (
+                "start",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","tool_calls":[{"index":0,"id":"call_a","type":"function","function":{"name":"get_weather","arguments":""}}]},"finish_reason":null}]}"#,
+                vec![("call_a", serde_json::Value::Null, vec![""])],
+            ),
+            (
+                "partial string",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"location\":\"Par"}}]},"finish_reason":null}]}"#,
+                vec![(
+                    "call_a",
+                    serde_json::json!({"location": "Par"}),
+                    vec![
+                        "",
+                        "/location",
+                    ],
+                )],
+            ),
+            (
+                "string done",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"is\","}}]},"finish_reason":null}]}"#,
+                vec![("call_a", serde_json::json!({"location": "Paris"}), vec![""])],
+            ),
+            (
+                "finish",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"unit\":\"c\"}"}}]},"finish_reason":"tool_calls"}]}"#,
+                vec![],
+            ),
+        ];
+        let mut parser = OpenaiEventDataParser::default();
+        for (name, data, want) in test_cases {
+            parser.parse_str(data).unwrap();
+            let got: Vec<_> = parser
+                .partial_tool_calls()
+                .into_iter()
+                .map(|call| {
+                    let arguments = call.arguments.unwrap();
+                    (call.id, arguments.value, arguments.incomplete)
+                })
+                .collect();
+            let want: Vec<_> = want
+                .into_iter()
+                .map(|(id, value, incomplete)| {
+                    (
+                        id.to_string(),
+                        value,
+                        incomplete.into_iter().map(String::from).collect::<Vec<_>>(),
+                    )
+                })
+                .collect();
+            assert_eq!(got, want, "test_parser failed: {}", name);
+        }
+    }
+
+    #[test]
+    fn test_parser_for_interleaved_tool_calls() {
+        let call = |id: &str, location: &str| {
+            ToolCall::Function(ToolCallFunction {
+                id: id.to_string(),
+                function: ToolCallFunctionObj {
+                    name: "get_weather".to_string(),
+                    arguments: format!(r#"{{"location":"{}"}}"#, location),
+                },
+            })
+        };
+        let test_cases = vec![
+            (
+                "both start in one delta",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","tool_calls":[{"index":0,"id":"call_a","type":"function","function":{"name":"get_weather","arguments":""}},{"index":1,"id":"call_b","type":"function","function":{"name":"get_weather","arguments":""}}]},"finish_reason":null}]}"#,
+                vec![],
+            ),
+            (
+                "interleaved arguments",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"{\"location\":"}},{"index":0,"function":{"arguments":"{\"location\":"}}]},"finish_reason":null}]}"#,
+                vec![],
+            ),
+            (
+                "index 0 done",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"Paris\"}"}},{"index":1,"function":{"arguments":"\"Tok"}}]},"finish_reason":null}]}"#,
+                vec![],
+            ),
+            (
+                "index 2 starts",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":2,"id":"call_c","type":"function","function":{"name":"get_weather","arguments":"{\"location\":\"Rome\"}"}}]},"finish_reason":null}]}"#,
+                vec![call(
+                    "call_a", "Paris",
+                )],
+            ),
+            (
+                "finish",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"yo\"}"}}]},"finish_reason":"tool_calls"}]}"#,
+                vec![
+                    call("call_b", "Tokyo"),
+                    call("call_c", "Rome"),
+                ],
+            ),
+            ("done", "[DONE]", vec![]),
+        ];
+        let mut parser = OpenaiEventDataParser::default();
+        for (name, data, want) in test_cases {
+            let got = parser.parse_str(data).unwrap();
+            assert_eq!(got, want, "test_parser failed: {}", name);
+        }
+        let res = parser.response();
+        assert_eq!(
+            res.choices[0].message.tool_calls,
+            Some(vec![
+                call("call_a", "Paris"),
+                call("call_b", "Tokyo"),
+                call("call_c", "Rome"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parser_for_tool_call_fragment_after_complete_args() {
+        let call = |id: &str, arguments: &str| {
+            ToolCall::Function(ToolCallFunction {
+                id: id.to_string(),
+                function: ToolCallFunctionObj {
+                    name: "get_weather".to_string(),
+                    arguments: arguments.to_string(),
+                },
+            })
+        };
+        let test_cases = vec![
+            (
+                "index 0 args parse",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","tool_calls":[{"index":0,"id":"call_a","type":"function","function":{"name":"get_weather","arguments":"{\"location\":\"Paris\"}"}}]},"finish_reason":null}]}"#,
+                vec![],
+            ),
+            (
+                "index 1 starts",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"id":"call_b","type":"function","function":{"name":"get_weather","arguments":"{\"location\":\"Tokyo\"}"}}]},"finish_reason":null}]}"#,
+                vec![call(
+                    "call_a",
+                    "{\"location\":\"Paris\"}",
+                )],
+            ),
+            (
+                "trailing fragment for index 0",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\n"}}]},"finish_reason":null}]}"#,
+                vec![],
+            ),
+            (
+                "finish",
+                r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#,
+                vec![call(
+                    "call_b",
+                    "{\"location\":\"Tokyo\"}",
+                )],
+            ),
+            ("done", "[DONE]", vec![]),
+        ];
+        let mut parser = OpenaiEventDataParser::default();
+        for (name, data, want) in test_cases {
+            let got = parser.parse_str(data).unwrap();
+            assert_eq!(got, want, "test_parser failed: {}", name);
+        }
+        let res = parser.response();
+        assert_eq!(
+            res.choices[0].message.tool_calls,
+            Some(vec![
+                call("call_a", "{\"location\":\"Paris\"}\n"),
+                call("call_b", "{\"location\":\"Tokyo\"}"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parser_for_usage() {
+        let test_cases = vec![
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","content":"Hi"},"finish_reason":null}],"usage":null}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{},"finish_reason":"stop"}],"usage":null}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[],"usage":{"prompt_tokens":19,"completion_tokens":10,"total_tokens":29,"prompt_tokens_details":{"cached_tokens":12,"audio_tokens":0},"completion_tokens_details":{"reasoning_tokens":4,"audio_tokens":0,"accepted_prediction_tokens":0,"rejected_prediction_tokens":0}}}"#,
+            "[DONE]",
+        ];
+        let mut parser = OpenaiEventDataParser::default();
+        for data in test_cases {
+            parser.parse_str(data).unwrap();
+        }
+        let want = Usage {
+            completion_tokens: 10,
+            prompt_tokens: 19,
+            total_tokens: 29,
+            completion_tokens_details: Some(CompletionTokensDetails {
+                accepted_prediction_tokens: Some(0),
+                audio_tokens: Some(0),
+                reasoning_tokens: Some(4),
+                rejected_prediction_tokens: Some(0),
+            }),
+            prompt_tokens_details: Some(PromptTokensDetails {
+                audio_tokens: Some(0),
+                cached_tokens: Some(12),
+            }),
+        };
+        assert_eq!(parser.usage(), Some(&want));
+        let res = parser.response();
+        assert_eq!(res.choices.len(), 1);
+        assert_eq!(res.choices[0].message.content.as_deref(), Some("Hi"));
+        assert_eq!(res.usage, want);
+    }
+
+    #[test]
+    fn test_parser_for_logprobs() {
+        let test_cases = vec![
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","content":""},"logprobs":{"content":[],"refusal":null},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":"Hello"},"logprobs":{"content":[{"token":"Hello","logprob":-0.31,"bytes":[72,101,108,108,111],"top_logprobs":[]}],"refusal":null},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":"!"},"logprobs":{"content":[{"token":"!","logprob":-0.02,"bytes":[33],"top_logprobs":[]}],"refusal":null},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o","choices":[{"index":0,"delta":{},"logprobs":null,"finish_reason":"stop"}]}"#,
+            "[DONE]",
+        ];
+        let mut parser = OpenaiEventDataParser::default();
+        for data in test_cases {
+            parser.parse_str(data).unwrap();
+        }
+        let res = parser.response();
+        assert_eq!(
+            res.choices[0].logprobs,
+            Some(Logprobs {
+                content: Some(vec![
+                    LogprobContent {
+                        token: "Hello".to_string(),
+                        logprob: -0.31,
+                        bytes: Some(vec![
+                            72, 101, 108, 108, 111
+                        ]),
+                        top_logprobs: Some(vec![]),
+                    },
+                    LogprobContent {
+                        token: "!".to_string(),
+                        logprob: -0.02,
+                        bytes: Some(vec![33]),
+                        top_logprobs: Some(vec![]),
+                    },
+                ]),
+                refusal: None,
+            })
+        );
+    }
+
+    #[test]
+    fn chunk_error() {
+        let tests = vec![
+            (
+                "api error",
+                r#"{"error":{"code":502,"message":"Provider returned error"}}"#,
+                "Provider returned error (code: 502)",
+            ),
+            (
+                "invalid json",
+                r#"{"id":"chatcmpl-123""#,
+                "EOF while parsing an object at line 1 column 20",
+            ),
+        ];
+        for (name, data, want) in tests {
+            let got = Chunk::from_str(data).unwrap_err();
+            assert_eq!(
+                matches!(got, ChunkError::Api(_)),
+                name == "api error",
+                "{}",
+                name
+            );
+            assert_eq!(got.to_string(), want, "chunk error test failed: {}", name);
+        }
+    }
+
+    #[test]
+    fn split_pieces() {
+        let tests = vec![
+            ("none", None, 4, vec![]),
+            ("empty", Some(""), 4, vec![]),
+            ("whole", Some("hello"), 0, vec!["hello"]),
+            (
+                "exact",
+                Some("abcdef"),
+                3,
+                vec![
+                    "abc", "def",
+                ],
+            ),
+            (
+                "multi-byte",
+                Some("你好世界!"),
+                2,
+                vec![
+                    "你好", "世界", "!",
+                ],
+            ),
+        ];
+        for (name, s, size, want) in tests {
+            assert_eq!(super::split_pieces(s, size), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn to_chunks() {
+        let response = ChatCompletionResponse {
+            id: "chatcmpl-123".to_string(),
+            object: "chat.completion".to_string(),
+            created: 1694268190,
+            model: "gpt-4o".to_string(),
+            system_fingerprint: Some("fp_44709d6fcb".to_string()),
+            service_tier: None,
+            choices: vec![
+                ChatCompletionChoice {
+                    index: 0,
+                    message: Message {
+                        role: Role::Assistant,
+                        reasoning: Some("Look up the weather.".to_string()),
+                        content: Some("Checking 北京 and Paris".to_string()),
+                        tool_calls: Some(vec![
+                            ToolCall::Function(ToolCallFunction {
+                                id: "call_1".to_string(),
+                                function: ToolCallFunctionObj {
+                                    name: "get_weather".to_string(),
+                                    arguments: r#"{"city":"北京"}"#.to_string(),
+                                },
+                            }),
+                            ToolCall::Function(ToolCallFunction {
+                                id: "call_2".to_string(),
+                                function: ToolCallFunctionObj {
+                                    name: "get_weather".to_string(),
+                                    arguments: r#"{"city":"Paris"}"#.to_string(),
+                                },
+                            }),
+                        ]),
+                        ..Default::default()
+                    },
+                    finish_reason: Some(FinishReason::ToolCalls),
+                    logprobs: None,
+                },
+                ChatCompletionChoice {
+                    index: 1,
+                    message: Message {
+                        role: Role::Assistant,
+                        content: Some("Hi".to_string()),
+                        ..Default::default()
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    logprobs: Some(Logprobs {
+                        content: Some(vec![
+                            LogprobContent {
+                                token: "Hi".to_string(),
+                                logprob: -0.1,
+                                bytes: None,
+                                top_logprobs: None,
+                            },
+                        ]),
+                        refusal: None,
+                    }),
+                },
+                ChatCompletionChoice {
+                    index: 2,
+                    message: Message {
+                        role: Role::Assistant,
+                        refusal: Some("I can't help with that.".to_string()),
+                        ..Default::default()
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    logprobs: None,
+                },
+            ],
+            usage: Usage {
+                completion_tokens: 10,
+                prompt_tokens: 19,
+                total_tokens: 29,
+                ..Default::default()
+            },
+        };
+
+        let tests = vec![
+            ("whole", 0, false, 15),
+            ("pieces", 4, true, 36),
+        ];
+        for (name, chunk_size, include_usage, len) in tests {
+            let options = ChunkOptions {
+                chunk_size,
+                include_usage,
+            };
+            let chunks = response.to_chunks(&options);
+            assert_eq!(chunks.len(), len, "{}", name);
+            assert_eq!(chunks.last(), Some(&Chunk::Done), "{}", name);
+
+            let mut parser = OpenaiEventDataParser::default();
+            let mut tool_calls = vec![];
+            for chunk in chunks.iter() {
+                tool_calls.extend(parser.parse(chunk).unwrap());
+            }
+            assert_eq!(
+                Some(tool_calls),
+                response.choices[0].message.tool_calls,
+                "{}",
+                name
+            );
+            let got = parser.response();
+            assert_eq!(
+                got.choices[2].message.refusal.as_deref(),
+                Some("I can't help with that."),
+                "{}",
+                name
+            );
+            assert_eq!(got.choices, response.choices, "{}", name);
+            assert_eq!(got.system_fingerprint, response.system_fingerprint);
+            if include_usage {
+                assert_eq!(got.usage, response.usage, "{}", name);
+            }
+        }
+    }
 }
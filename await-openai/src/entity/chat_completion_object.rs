@@ -1,6 +1,6 @@
 use std::fmt;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use super::create_chat_completion::{FinishReason, ToolCall};
 
@@ -9,14 +9,43 @@ pub struct ErrResponse {
     pub error: Err,
 }
 
+/// The error object of an Openai compatible API.
+/// Missing fields default to empty, `param` and `code` may also be `null` or a number, e.g. from vLLM and OpenRouter.
 #[derive(Debug, Deserialize, Clone, Default, PartialEq, Serialize)]
 pub struct Err {
+    #[serde(default)]
     pub message: String,
+    #[serde(default)]
     pub r#type: String,
+    #[serde(default, deserialize_with = "string_or_number")]
     pub param: String,
+    #[serde(default, deserialize_with = "string_or_number")]
     pub code: String,
 }
 
+impl fmt::Display for Err {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.r#type.is_empty() {
+            write!(f, "{}: ", self.r#type)?;
+        }
+        write!(f, "{}", self.message)?;
+        if !self.code.is_empty() {
+            write!(f, " (code: {})", self.code)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Err {}
+
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Null => String::new(),
+        v => v.to_string(),
+    })
+}
+
 #[derive(Debug, Deserialize, Clone, Default, PartialEq, Serialize)]
 pub struct Response {
     /// A unique identifier for the chat completion.
@@ -327,6 +356,54 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn err_response() {
+        let tests = vec![
+            (
+                "openai",
+                r#"{"error":{"message":"Rate limit reached","type":"requests","param":null,"code":"rate_limit_exceeded"}}"#,
+                Err {
+                    message: "Rate limit reached".to_string(),
+                    r#type: "requests".to_string(),
+                    param: "".to_string(),
+                    code: "rate_limit_exceeded".to_string(),
+                },
+                "requests: Rate limit reached (code: rate_limit_exceeded)",
+            ),
+            (
+                "openrouter",
+                r#"{"error":{"code":502,"message":"Provider returned error","metadata":{"provider_name":"Anthropic"}}}"#,
+                Err {
+                    message: "Provider returned error".to_string(),
+                    code: "502".to_string(),
+                    ..Default::default()
+                },
+                "Provider returned error (code: 502)",
+            ),
+            (
+                "vllm",
+                r#"{"error":{"object":"error","message":"max_tokens is too large","type":"BadRequestError","param":null,"code":400}}"#,
+                Err {
+                    message: "max_tokens is too large".to_string(),
+                    r#type: "BadRequestError".to_string(),
+                    param: "".to_string(),
+                    code: "400".to_string(),
+                },
+                "BadRequestError: max_tokens is too large (code: 400)",
+            ),
+        ];
+        for (name, json, want, display) in tests {
+            let got = serde_json::from_str::<ErrResponse>(json).unwrap();
+            assert_eq!(got.error, want, "deserialize test failed: {}", name);
+            assert_eq!(
+                got.error.to_string(),
+                display,
+                "display test failed: {}",
+                name
+            );
+        }
+    }
+
     #[test]
     fn serde() {
         let tests = vec![
@@ -658,8 +658,9 @@ pub struct ToolMessage {
 /// Controls which (if any) function is called by the model.
 /// `none` means the model will not call a function and instead generates a message.
 /// `auto` means the model can pick between generating a message or calling a function.
+/// `required` means the model must call one or more functions.
 /// Specifying a particular function via `{"type: "function", "function": {"name": "my_function"}}` forces the model to call that function.
-
+///
 /// `none` is the default when no functions are present. `auto` is the default if functions are present.
 #[derive(Clone, Serialize, Default, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -667,6 +668,7 @@ pub enum ToolChoice {
     #[default]
     None,
     Auto,
+    Required,
     #[serde(untagged)]
     Function(ToolChoiceFunction),
 }
@@ -1,45 +1,1070 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use crate::{
+    convert::{
+        ConversionError, ConversionIssue, ConversionMode, Converted, Reporter,
+        max_completion_tokens,
+    },
     entity::{
         chat_completion_chunk::{
-            Choice, Chunk, ChunkResponse, DeltaMessage, OpenaiEventDataParser,
+            Choice, Chunk, ChunkResponse, DeltaMessage, OpenaiEventDataParser, ToolCallChunk,
+            ToolCallFunctionObjChunk,
         },
         chat_completion_object::{
-            Response as OpenaiResponse, Role as OpenaiRole, Usage as OpenaiUsage,
+            CompletionTokensDetails, PromptTokensDetails, Response as OpenaiResponse,
+            Role as OpenaiRole, Usage as OpenaiUsage,
         },
         create_chat_completion::{
-            Content, ContentPart, FinishReason, Message as OpenaiMessage,
-            RequestBody as OpenaiRequestBody, Stop,
+            Content as OpenaiContent, ContentPart, FinishReason as OpenaiFinishReason,
+            Message as OpenaiMessage, ReasoningEffort, RequestBody as OpenaiRequestBody,
+            ResponseFormat, Stop, Tool as OpenaiTool, ToolCall, ToolCallFunction,
+            ToolCallFunctionObj, ToolChoice as OpenaiToolChoice,
         },
     },
     magi::EventDataParser,
+    sse::{FromSseEvent, SseEvent},
 };
 pub use async_gemini::models::*;
 
-//TODO this are serious problems in gemini function call
 impl From<OpenaiRequestBody> for GenerateContentRequest {
     fn from(body: OpenaiRequestBody) -> Self {
-        let mut stops = Option::None;
-        if let Some(ss) = body.stop {
-            match ss {
-                Stop::String(s) => stops = Some(vec![s]),
-                Stop::Array(a) => stops = Some(a),
+        convert_with_report(body, ConversionMode::Lossy)
+            .expect("lossy conversion never fails")
+            .request
+    }
+}
+
+/// Convert an Openai request to a Gemini request and report what Gemini can't express.
+/// In strict mode the first dropped or degraded field is returned as an error.
+pub fn convert_with_report(
+    body: OpenaiRequestBody,
+    mode: ConversionMode,
+) -> Result<Converted<GenerateContentRequest>, ConversionError> {
+    let mut reporter = Reporter::new(mode);
+    report_unsupported_fields(&body, &mut reporter)?;
+    let max_output_tokens = max_completion_tokens(&body, &mut reporter)?;
+
+    let mut system_parts = vec![];
+    let mut contents = Vec::with_capacity(body.messages.len());
+    // Gemini answers a function call by name, Openai by the id of the call.
+    let mut function_names: HashMap<String, String> = HashMap::new();
+    for (i, message) in body.messages.into_iter().enumerate() {
+        match message {
+            OpenaiMessage::System(system) => {
+                if system.name.is_some() {
+                    reporter
+                        .report(ConversionIssue::dropped("name", NOT_SUPPORTED).at_message(i))?;
+                }
+                system_parts.push(Part::Text(system.content));
+            }
+            OpenaiMessage::User(user) => {
+                if user.name.is_some() {
+                    reporter
+                        .report(ConversionIssue::dropped("name", NOT_SUPPORTED).at_message(i))?;
+                }
+                let parts = match user.content {
+                    OpenaiContent::Text(text) => vec![Part::Text(text)],
+                    OpenaiContent::Array(parts) => {
+                        let mut converted = Vec::with_capacity(parts.len());
+                        for (j, part) in parts.into_iter().enumerate() {
+                            let (field, url, mime_type) = match part {
+                                ContentPart::Text(text_part) => {
+                                    converted.push(Part::Text(text_part.text));
+                                    continue;
+                                }
+                                ContentPart::Image(image_part) => {
+                                    ("image_url", image_part.image_url.url, None)
+                                }
+                                #[cfg(feature = "custom_content_part")]
+                                ContentPart::Document(document) => {
+                                    ("document", document.url, document.mime_type)
+                                }
+                                #[cfg(feature = "custom_content_part")]
+                                ContentPart::Audio(audio) => ("audio", audio.url, None),
+                            };
+                            match media_part(url, mime_type) {
+                                Ok(part) => converted.push(part),
+                                Err(reason) => reporter.report(
+                                    ConversionIssue::dropped(field, reason)
+                                        .at_message(i)
+                                        .at_part(j),
+                                )?,
+                            }
+                        }
+                        converted
+                    }
+                };
+                contents.push(Content {
+                    role: Role::User,
+                    parts,
+                });
+            }
+            OpenaiMessage::Assistant(assistant) => {
+                if assistant.name.is_some() {
+                    reporter
+                        .report(ConversionIssue::dropped("name", NOT_SUPPORTED).at_message(i))?;
+                }
+                let mut parts = vec![];
+                if let Some(text) = assistant.content.filter(|t| !t.is_empty()) {
+                    parts.push(Part::Text(text));
+                }
+                for ToolCall::Function(call) in assistant.tool_calls.into_iter().flatten() {
+                    let args = if call.function.arguments.trim().is_empty() {
+                        None
+                    } else {
+                        match serde_json::from_str(&call.function.arguments) {
+                            Ok(args) => Some(args),
+                            Err(_) => {
+                                reporter.report(
+                                    ConversionIssue::degraded(
+                                        "arguments",
+                                        "invalid JSON, sent without arguments",
+                                    )
+                                    .at_message(i),
+                                )?;
+                                None
+                            }
+                        }
+                    };
+                    function_names.insert(call.id, call.function.name.clone());
+                    parts.push(Part::FunctionCall(FunctionCall {
+                        name: call.function.name,
+                        args,
+                    }));
+                }
+                if !parts.is_empty() {
+                    contents.push(Content {
+                        role: Role::Model,
+                        parts,
+                    });
+                }
+            }
+            OpenaiMessage::Tool(tool) => {
+                let name = match function_names.get(&tool.tool_call_id) {
+                    Some(name) => name.clone(),
+                    None => {
+                        reporter.report(
+                            ConversionIssue::degraded(
+                                "tool_call_id",
+                                "no matching tool call, the id is used as the function name",
+                            )
+                            .at_message(i),
+                        )?;
+                        tool.tool_call_id
+                    }
+                };
+                contents.push(Content {
+                    role: Role::User,
+                    parts: vec![
+                        Part::FunctionResponse(FunctionResponse {
+                            name,
+                            response: function_response(tool.content),
+                        }),
+                    ],
+                });
             }
         }
+    }
 
-        let contents: Vec<Content> = Vec::with_capacity(body.messages.len());
+    let (response_mime_type, response_json_schema) = match body.response_format {
+        None | Some(ResponseFormat::Text) => (None, None),
+        Some(ResponseFormat::JsonObject) => (Some("application/json".to_string()), None),
+        Some(ResponseFormat::JsonSchema { properties, .. }) => {
+            (Some("application/json".to_string()), properties)
+        }
+    };
 
-        GenerateContentRequest {
-            contents: vec![],
-            tools: None,
-            safety_settings: None,
-            generation_config: Some(GenerateionConfig {
-                temperature: body.temperature,
-                top_p: body.top_p,
-                top_k: None,
-                candidate_count: None,
-                max_output_tokens: body.max_completion_tokens,
-                stop_sequences: stops,
+    let seed = match body.seed.map(i32::try_from) {
+        Some(Ok(seed)) => Some(seed),
+        Some(Err(_)) => {
+            reporter.report(ConversionIssue::dropped(
+                "seed",
+                "Gemini takes a 32-bit seed",
+            ))?;
+            None
+        }
+        None => None,
+    };
+
+    let mut stops = Option::None;
+    if let Some(ss) = body.stop {
+        match ss {
+            Stop::String(s) => stops = Some(vec![s]),
+            Stop::Array(a) => stops = Some(a),
+        }
+    }
+
+    Ok(reporter.finish(GenerateContentRequest {
+        contents: process_contents(&contents),
+        tools: body.tools.map(|tools| {
+            vec![Tool {
+                function_declarations: tools.into_iter().map(FunctionTool::from).collect(),
+            }]
+        }),
+        tool_config: body.tool_choice.map(|choice| ToolConfig {
+            function_calling_config: match choice {
+                OpenaiToolChoice::None => FunctionCallingConfig {
+                    mode: FunctionCallingMode::None,
+                    allowed_function_names: None,
+                },
+                OpenaiToolChoice::Auto => FunctionCallingConfig {
+                    mode: FunctionCallingMode::Auto,
+                    allowed_function_names: None,
+                },
+                OpenaiToolChoice::Required => FunctionCallingConfig {
+                    mode: FunctionCallingMode::Any,
+                    allowed_function_names: None,
+                },
+                OpenaiToolChoice::Function(f) => FunctionCallingConfig {
+                    mode: FunctionCallingMode::Any,
+                    allowed_function_names: Some(vec![f.function.name]),
+                },
+            },
+        }),
+        safety_settings: None,
+        system_instruction: (!system_parts.is_empty()).then_some(Content {
+            role: Role::User,
+            parts: system_parts,
+        }),
+        generation_config: Some(GenerationConfig {
+            temperature: body.temperature,
+            top_p: body.top_p,
+            candidate_count: body.n.map(u32::from),
+            max_output_tokens,
+            stop_sequences: stops,
+            response_mime_type,
+            response_json_schema,
+            presence_penalty: body.presence_penalty,
+            frequency_penalty: body.frequency_penalty,
+            seed,
+            response_logprobs: body.logprobs,
+            logprobs: body.top_logprobs.map(i32::from),
+            thinking_config: body.reasoning_effort.map(|effort| ThinkingConfig {
+                thinking_budget: Some(thinking_budget(effort)),
+                // thoughts are returned as reasoning, the same as Claude's thinking
+                include_thoughts: Some(true),
             }),
+            ..Default::default()
+        }),
+    }))
+}
+
+fn thinking_budget(effort: ReasoningEffort) -> i32 {
+    match effort {
+        ReasoningEffort::Low => 1024,
+        ReasoningEffort::Medium => 8192,
+        ReasoningEffort::High => 24576,
+    }
+}
+
+impl From<OpenaiTool> for FunctionTool {
+    fn from(tool: OpenaiTool) -> Self {
+        FunctionTool {
+            name: tool.function.name.into_owned(),
+            description: tool.function.description.map(|d| d.into_owned()),
+            parameters: tool.function.parameters,
         }
     }
 }
+
+const NOT_SUPPORTED: &str = "not supported by Gemini";
+
+fn report_unsupported_fields(
+    body: &OpenaiRequestBody,
+    reporter: &mut Reporter,
+) -> Result<(), ConversionError> {
+    let unsupported = [
+        ("logit_bias", body.logit_bias.is_some()),
+        ("reasoning", body.reasoning.is_some()),
+        ("audio", body.audio.is_some()),
+        ("prediction", body.prediction.is_some()),
+        ("web_search_options", body.web_search_options.is_some()),
+        (
+            "parallel_tool_calls",
+            body.parallel_tool_calls == Some(false),
+        ),
+        ("user", body.user.is_some()),
+        ("metadata", body.metadata.is_some()),
+        ("store", body.store == Some(true)),
+        (
+            "modalities",
+            body.modalities
+                .as_ref()
+                .is_some_and(|m| m.iter().any(|m| m != "text")),
+        ),
+        ("service_tier", body.service_tier.is_some()),
+    ];
+    for (field, present) in unsupported {
+        if present {
+            reporter.report(ConversionIssue::dropped(field, NOT_SUPPORTED))?;
+        }
+    }
+    Ok(())
+}
+
+/// Data urls are sent inline, anything else is passed as a file uri.
+/// Gemini doesn't fetch arbitrary web urls, only files uploaded with the Files API.
+fn media_part(url: String, mime_type: Option<String>) -> Result<Part, &'static str> {
+    if let Some(rest) = url.strip_prefix("data:") {
+        let (mime_type, data) = rest.split_once(";base64,").ok_or("unknown mime type")?;
+        return Ok(Part::Inline(InlineData {
+            mime_type: mime_type.to_string(),
+            data: data.to_string(),
+            video_metadata: None,
+        }));
+    }
+    if (url.starts_with("http://") || url.starts_with("https://"))
+        && !url.starts_with("https://generativelanguage.googleapis.com/")
+    {
+        return Err("Gemini only reads uploaded files, send web images as data urls");
+    }
+    let mime_type = mime_type
+        .or_else(|| mime_from_extension(&url).map(String::from))
+        .ok_or("unknown mime type")?;
+    Ok(Part::File(FileData {
+        mime_type,
+        file_uri: url,
+        video_metadata: None,
+    }))
+}
+
+fn mime_from_extension(url: &str) -> Option<&'static str> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let (_, extension) = path.rsplit_once('.')?;
+    let mime = match extension.to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "heic" => "image/heic",
+        "heif" => "image/heif",
+        "gif" => "image/gif",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        "mp3" => "audio/mp3",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "flac" => "audio/flac",
+        "aac" => "audio/aac",
+        "mp4" => "video/mp4",
+        "mov" => "video/mov",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// Gemini expects the function response as an object, plain text is wrapped in `{"content": ...}`.
+fn function_response(content: String) -> serde_json::Value {
+    match serde_json::from_str::<serde_json::Value>(&content) {
+        Ok(value) if value.is_object() => value,
+        _ => serde_json::json!({ "content": content }),
+    }
+}
+
+/// GeminiEventDataParser can convert streamed responses from Gemini API to Openai API.
+/// It stores the intermidiate state of the parsing result and can be used to generate Openai's unary response.
+/// Gemini sends a function call in one piece, so the complete tool calls are returned with the chunk that carries them.
+/// Gemini doesn't send a terminating event, map the end of the stream to `Chunk::Done` if you need one.
+///
+/// # Example
+///
+/// ````
+/// use await_openai::gemini::GeminiEventDataParser;
+///
+/// let mut parser = GeminiEventDataParser::default();
+/// let data = r#"{"candidates": [{"content": {"parts": [{"text": "Hello"}],"role": "model"},"index": 0}]}"#;
+/// let (chunk, tool_calls) = parser.parse_str(data).unwrap();
+/// assert!(chunk.is_some());
+/// assert!(tool_calls.is_empty());
+/// ````
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeminiEventDataParser {
+    usage: OpenaiUsage,
+    parser: OpenaiEventDataParser,
+    /// Number of tool calls seen for each candidate, a candidate is started once it's in the map.
+    tool_calls: HashMap<u32, usize>,
+}
+
+impl Default for GeminiEventDataParser {
+    fn default() -> Self {
+        let mut parser = OpenaiEventDataParser::default();
+        parser.created = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(n) => n.as_secs(),
+            Err(_) => 0,
+        };
+        Self {
+            usage: OpenaiUsage::default(),
+            parser,
+            tool_calls: HashMap::new(),
+        }
+    }
+}
+
+impl EventDataParser<GenerateContentResponse> for GeminiEventDataParser {
+    type Error = Infallible;
+    type Output = (Option<Chunk>, Vec<ToolCall>);
+    type UnarayResponse = OpenaiResponse;
+
+    fn parse(
+        &mut self,
+        data: &GenerateContentResponse,
+    ) -> Result<(Option<Chunk>, Vec<ToolCall>), Infallible> {
+        if let Some(id) = data.response_id.as_ref() {
+            self.parser.update_id_if_empty(id);
+        }
+        if let Some(model) = data.model_version.as_ref() {
+            self.parser.update_model_if_empty(model);
+        }
+        // the usage metadata is a running count, the last one wins
+        if let Some(usage) = data.usage_metadata {
+            self.usage = usage.into();
+        }
+
+        let mut choices = Vec::with_capacity(data.candidates.len());
+        let mut tool_calls = vec![];
+        for candidate in data.candidates.iter() {
+            let (choice, calls) = self.parse_candidate(candidate);
+            choices.push(choice);
+            tool_calls.extend(calls);
+        }
+        // the prompt is blocked, no candidate is generated
+        if data.candidates.is_empty()
+            && data
+                .prompt_feedback
+                .as_ref()
+                .is_some_and(|feedback| feedback.block_reason.is_some())
+        {
+            choices.push(Choice {
+                index: 0,
+                finish_reason: Some(OpenaiFinishReason::ContentFilter),
+                ..Default::default()
+            });
+        }
+
+        if choices.is_empty() {
+            return Ok((None, tool_calls));
+        }
+        let chunk = Chunk::Data(ChunkResponse {
+            id: self.parser.id.to_string(),
+            choices,
+            created: self.parser.created,
+            model: self.parser.model.to_string(),
+            system_fingerprint: None,
+            service_tier: None,
+            object: "chat.completion.chunk".to_string(),
+            usage: None,
+        });
+        // every candidate becomes a choice of the unary response
+        let Ok(_) = self.parser.parse(&chunk);
+        Ok((Some(chunk), tool_calls))
+    }
+
+    fn response(mut self) -> OpenaiResponse {
+        self.parser.object = "chat.completion".to_string();
+        let mut res = self.parser.response();
+        res.usage = self.usage;
+        res
+    }
+}
+
+impl GeminiEventDataParser {
+    pub fn parse_str(
+        &mut self,
+        d: &str,
+    ) -> Result<(Option<Chunk>, Vec<ToolCall>), serde_json::Error> {
+        let payload = serde_json::from_str::<GenerateContentResponse>(d)?;
+        let Ok(parsed) = self.parse(&payload);
+        Ok(parsed)
+    }
+
+    pub fn parse_value(
+        &mut self,
+        d: serde_json::Value,
+    ) -> Result<(Option<Chunk>, Vec<ToolCall>), serde_json::Error> {
+        let payload = serde_json::from_value::<GenerateContentResponse>(d)?;
+        let Ok(parsed) = self.parse(&payload);
+        Ok(parsed)
+    }
+
+    fn parse_candidate(&mut self, candidate: &Candidate) -> (Choice, Vec<ToolCall>) {
+        let role =
+            (!self.tool_calls.contains_key(&candidate.index)).then_some(OpenaiRole::Assistant);
+        let count = self.tool_calls.entry(candidate.index).or_default();
+
+        let mut text = String::new();
+        let mut reasoning = String::new();
+        let mut tool_call_chunks = vec![];
+        let mut tool_calls = vec![];
+        for part in candidate.content.parts.iter() {
+            match part {
+                Part::Text(t) => text.push_str(t),
+                Part::Thought(t) => reasoning.push_str(t),
+                Part::FunctionCall(call) => {
+                    let arguments = call
+                        .args
+                        .as_ref()
+                        .map(|args| args.to_string())
+                        .unwrap_or_else(|| "{}".to_string());
+                    // Gemini doesn't id the function calls
+                    let id = format!("call_{}_{}", candidate.index, count);
+                    tool_call_chunks.push(ToolCallChunk {
+                        index: *count,
+                        id: Some(id.clone()),
+                        r#type: Some("function".to_string()),
+                        function: ToolCallFunctionObjChunk {
+                            name: Some(call.name.clone()),
+                            arguments: arguments.clone(),
+                        },
+                    });
+                    tool_calls.push(ToolCall::Function(ToolCallFunction {
+                        id,
+                        function: ToolCallFunctionObj {
+                            name: call.name.clone(),
+                            arguments,
+                        },
+                    }));
+                    *count += 1;
+                }
+                Part::Inline(_) | Part::FunctionResponse(_) | Part::File(_) | Part::Unknown(_) => {}
+            }
+        }
+
+        let finish_reason = candidate.finish_reason.map(|reason| {
+            // Gemini stops with `STOP` after a function call
+            match OpenaiFinishReason::from(reason) {
+                OpenaiFinishReason::Stop if *count > 0 => OpenaiFinishReason::ToolCalls,
+                reason => reason,
+            }
+        });
+
+        let choice = Choice {
+            index: candidate.index as usize,
+            delta: DeltaMessage {
+                role,
+                content: (!text.is_empty()).then_some(text),
+                reasoning: (!reasoning.is_empty()).then_some(reasoning),
+                tool_calls: (!tool_call_chunks.is_empty()).then_some(tool_call_chunks),
+                ..Default::default()
+            },
+            finish_reason,
+            logprobs: None,
+        };
+        (choice, tool_calls)
+    }
+}
+
+/// Decodes the stream of `streamGenerateContent?alt=sse`.
+impl FromSseEvent for GenerateContentResponse {
+    type Error = serde_json::Error;
+
+    fn from_sse_event(event: &SseEvent) -> Result<Self, Self::Error> {
+        serde_json::from_str(&event.data)
+    }
+}
+
+impl From<FinishReason> for OpenaiFinishReason {
+    fn from(reason: FinishReason) -> Self {
+        match reason {
+            FinishReason::MaxTokens => OpenaiFinishReason::Length,
+            FinishReason::Safety
+            | FinishReason::Recitation
+            | FinishReason::Blocklist
+            | FinishReason::ProhibitedContent
+            | FinishReason::Spii
+            | FinishReason::ImageSafety => OpenaiFinishReason::ContentFilter,
+            FinishReason::Unspecified
+            | FinishReason::Stop
+            | FinishReason::Language
+            | FinishReason::Other
+            | FinishReason::MalformedFunctionCall
+            | FinishReason::Unknown => OpenaiFinishReason::Stop,
+        }
+    }
+}
+
+impl From<UsageMetadata> for OpenaiUsage {
+    fn from(usage: UsageMetadata) -> Self {
+        // Openai counts the reasoning tokens as completion tokens
+        let completion_tokens =
+            usage.candidates_token_count + usage.thoughts_token_count.unwrap_or_default();
+        OpenaiUsage {
+            completion_tokens,
+            prompt_tokens: usage.prompt_token_count,
+            total_tokens: usage.prompt_token_count + completion_tokens,
+            completion_tokens_details: usage.thoughts_token_count.map(|reasoning_tokens| {
+                CompletionTokensDetails {
+                    reasoning_tokens: Some(reasoning_tokens),
+                    ..Default::default()
+                }
+            }),
+            prompt_tokens_details: usage.cached_content_token_count.map(|cached_tokens| {
+                PromptTokensDetails {
+                    cached_tokens: Some(cached_tokens),
+                    ..Default::default()
+                }
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        convert::{ConversionError, ConversionIssue, ConversionMode},
+        entity::create_chat_completion::RequestBody,
+    };
+
+    use super::*;
+
+    #[test]
+    fn convert_request() {
+        let tests = vec![
+            (
+                "system and text",
+                r#"{"model":"gemini-1.5-pro","messages":[{"role":"system","content":"You are a helpful assistant."},{"role":"user","content":"Hello!"}],"temperature":0.5}"#,
+                GenerateContentRequest {
+                    system_instruction: Some(Content {
+                        role: Role::User,
+                        parts: vec![Part::Text("You are a helpful assistant.".to_string())],
+                    }),
+                    contents: vec![Content {
+                        role: Role::User,
+                        parts: vec![Part::Text("Hello!".to_string())],
+                    }],
+                    generation_config: Some(GenerationConfig {
+                        temperature: Some(0.5),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            ),
+            (
+                "images",
+                r#"{"model":"gemini-1.5-pro","messages":[{"role":"user","content":[{"type":"text","text":"Compare these."},{"type":"image_url","image_url":{"url":"data:image/png;base64,iVBORw0KGgo"}},{"type":"image_url","image_url":{"url":"gs://bucket/cat.jpg"}}]}]}"#,
+                GenerateContentRequest {
+                    contents: vec![Content {
+                        role: Role::User,
+                        parts: vec![
+                            Part::Text("Compare these.".to_string()),
+                            Part::Inline(InlineData {
+                                mime_type: "image/png".to_string(),
+                                data: "iVBORw0KGgo".to_string(),
+                                video_metadata: None,
+                            }),
+                            Part::File(FileData {
+                                mime_type: "image/jpeg".to_string(),
+                                file_uri: "gs://bucket/cat.jpg".to_string(),
+                                video_metadata: None,
+                            }),
+                        ],
+                    }],
+                    generation_config: Some(GenerationConfig::default()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "generation config",
+                r#"{"model":"gemini-2.5-flash","messages":[{"role":"user","content":"List a few cookies."}],"n":2,"seed":7,"presence_penalty":0.5,"logprobs":true,"top_logprobs":3,"reasoning_effort":"medium","response_format":{"type":"json_schema","name":"cookies","properties":{"type":"array","items":{"type":"string"}}}}"#,
+                GenerateContentRequest {
+                    contents: vec![Content {
+                        role: Role::User,
+                        parts: vec![Part::Text("List a few cookies.".to_string())],
+                    }],
+                    generation_config: Some(GenerationConfig {
+                        candidate_count: Some(2),
+                        response_mime_type: Some("application/json".to_string()),
+                        response_json_schema: Some(
+                            json!({"type": "array", "items": {"type": "string"}}),
+                        ),
+                        presence_penalty: Some(0.5),
+                        seed: Some(7),
+                        response_logprobs: Some(true),
+                        logprobs: Some(3),
+                        thinking_config: Some(ThinkingConfig {
+                            thinking_budget: Some(8192),
+                            include_thoughts: Some(true),
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            ),
+            (
+                "function calling",
+                r#"{"model":"gemini-1.5-pro","messages":[{"role":"user","content":"Weather in Boston and Tokyo?"},{"role":"assistant","content":null,"tool_calls":[{"id":"call_1","type":"function","function":{"name":"get_weather","arguments":"{\"location\":\"Boston\"}"}},{"id":"call_2","type":"function","function":{"name":"get_weather","arguments":"{\"location\":\"Tokyo\"}"}}]},{"role":"tool","tool_call_id":"call_1","content":"12 degrees"},{"role":"tool","tool_call_id":"call_2","content":"{\"temperature\":20}"}],"tools":[{"type":"function","function":{"name":"get_weather","description":"Get the weather","parameters":{"type":"object","properties":{"location":{"type":"string"}}}}}],"tool_choice":{"type":"function","function":{"name":"get_weather"}}}"#,
+                GenerateContentRequest {
+                    contents: vec![
+                        Content {
+                            role: Role::User,
+                            parts: vec![Part::Text("Weather in Boston and Tokyo?".to_string())],
+                        },
+                        Content {
+                            role: Role::Model,
+                            parts: vec![
+                                Part::FunctionCall(FunctionCall {
+                                    name: "get_weather".to_string(),
+                                    args: Some(json!({"location": "Boston"})),
+                                }),
+                                Part::FunctionCall(FunctionCall {
+                                    name: "get_weather".to_string(),
+                                    args: Some(json!({"location": "Tokyo"})),
+                                }),
+                            ],
+                        },
+                        Content {
+                            role: Role::User,
+                            parts: vec![
+                                Part::FunctionResponse(FunctionResponse {
+                                    name: "get_weather".to_string(),
+                                    response: json!({"content": "12 degrees"}),
+                                }),
+                                Part::FunctionResponse(FunctionResponse {
+                                    name: "get_weather".to_string(),
+                                    response: json!({"temperature": 20}),
+                                }),
+                            ],
+                        },
+                    ],
+                    tools: Some(vec![Tool {
+                        function_declarations: vec![
+                            FunctionTool {
+                                name: "get_weather".to_string(),
+                                description: Some("Get the weather".to_string()),
+                                parameters: Some(json!({
+                                    "type": "object",
+                                    "properties": {"location": {"type": "string"}}
+                                })),
+                            },
+                        ],
+                    }]),
+                    tool_config: Some(ToolConfig {
+                        function_calling_config: FunctionCallingConfig {
+                            mode: FunctionCallingMode::Any,
+                            allowed_function_names: Some(vec!["get_weather".to_string()]),
+                        },
+                    }),
+                    generation_config: Some(GenerationConfig::default()),
+                    ..Default::default()
+                },
+            ),
+        ];
+        for (name, json, want) in tests {
+            let body: RequestBody = serde_json::from_str(json).unwrap();
+            let got: GenerateContentRequest = body.into();
+            assert_eq!(got, want, "convert request test failed: {}", name);
+        }
+    }
+
+    #[test]
+    fn conversion_report() {
+        let json = r#"{"model":"gemini-1.5-pro","messages":[{"role":"user","content":[{"type":"image_url","image_url":{"url":"gs://bucket/image"}},{"type":"image_url","image_url":{"url":"https://example.com/cat.png"}}]},{"role":"tool","tool_call_id":"call_1","content":"done"}],"logit_bias":{"50256":-100}}"#;
+        let body: RequestBody = serde_json::from_str(json).unwrap();
+
+        let converted = convert_with_report(body.clone(), ConversionMode::Lossy).unwrap();
+        assert_eq!(
+            converted.report.issues,
+            vec![
+                ConversionIssue::dropped("logit_bias", "not supported by Gemini"),
+                ConversionIssue::dropped("image_url", "unknown mime type")
+                    .at_message(0)
+                    .at_part(0),
+                ConversionIssue::dropped(
+                    "image_url",
+                    "Gemini only reads uploaded files, send web images as data urls"
+                )
+                .at_message(0)
+                .at_part(1),
+                ConversionIssue::degraded(
+                    "tool_call_id",
+                    "no matching tool call, the id is used as the function name"
+                )
+                .at_message(1),
+            ]
+        );
+
+        let err = convert_with_report(body, ConversionMode::Strict).unwrap_err();
+        assert_eq!(
+            err,
+            ConversionError(ConversionIssue::dropped(
+                "logit_bias",
+                "not supported by Gemini"
+            ))
+        );
+    }
+
+    #[test]
+    fn max_tokens() {
+        let tests = vec![
+            (
+                "max_completion_tokens",
+                r#"{"model":"gemini-1.5-pro","messages":[{"role":"user","content":"Hello!"}],"max_completion_tokens":1024}"#,
+                Some(1024),
+                vec![],
+            ),
+            (
+                "deprecated max_tokens",
+                r#"{"model":"gemini-1.5-pro","messages":[{"role":"user","content":"Hello!"}],"max_tokens":512}"#,
+                Some(512),
+                vec![],
+            ),
+            (
+                "conflicting",
+                r#"{"model":"gemini-1.5-pro","messages":[{"role":"user","content":"Hello!"}],"max_completion_tokens":1024,"max_tokens":512}"#,
+                Some(1024),
+                vec![
+                    ConversionIssue::dropped(
+                        "max_tokens",
+                        "conflicts with max_completion_tokens, which is used",
+                    ),
+                ],
+            ),
+            (
+                "missing",
+                r#"{"model":"gemini-1.5-pro","messages":[{"role":"user","content":"Hello!"}]}"#,
+                None,
+                vec![],
+            ),
+        ];
+        for (name, json, want_max_tokens, want_issues) in tests {
+            let body: RequestBody = serde_json::from_str(json).unwrap();
+            let got = convert_with_report(body.clone(), ConversionMode::Lossy).unwrap();
+            assert_eq!(
+                got.request
+                    .generation_config
+                    .and_then(|config| config.max_output_tokens),
+                want_max_tokens,
+                "{}",
+                name
+            );
+            assert_eq!(got.report.issues, want_issues, "{}", name);
+            let strict = convert_with_report(body, ConversionMode::Strict);
+            assert_eq!(strict.is_ok(), want_issues.is_empty(), "strict: {}", name);
+        }
+    }
+
+    #[test]
+    fn process_stream() {
+        let mut parser = GeminiEventDataParser::default();
+        let created = parser.parser.created;
+        let chunk = |choice: Choice| {
+            Some(Chunk::Data(ChunkResponse {
+                id: "resp_1".to_string(),
+                choices: vec![choice],
+                created,
+                model: "gemini-2.0-flash".to_string(),
+                object: "chat.completion.chunk".to_string(),
+                ..Default::default()
+            }))
+        };
+        let tool_call = ToolCall::Function(ToolCallFunction {
+            id: "call_0_0".to_string(),
+            function: ToolCallFunctionObj {
+                name: "get_weather".to_string(),
+                arguments: r#"{"location":"Boston"}"#.to_string(),
+            },
+        });
+
+        let events = vec![
+            (
+                "text",
+                r#"{"candidates": [{"content": {"parts": [{"text": "Weather question.","thought": true},{"text": "Let me check"}],"role": "model"},"index": 0}],"usageMetadata": {"promptTokenCount": 20,"totalTokenCount": 20},"modelVersion": "gemini-2.0-flash","responseId": "resp_1"}"#,
+                chunk(Choice {
+                    index: 0,
+                    delta: DeltaMessage {
+                        role: Some(OpenaiRole::Assistant),
+                        content: Some("Let me check".to_string()),
+                        reasoning: Some("Weather question.".to_string()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }),
+                vec![],
+            ),
+            (
+                "function call",
+                r#"{"candidates": [{"content": {"parts": [{"text": " the weather."},{"functionCall": {"name": "get_weather","args": {"location": "Boston"}}}],"role": "model"},"index": 0}],"modelVersion": "gemini-2.0-flash","responseId": "resp_1"}"#,
+                chunk(Choice {
+                    index: 0,
+                    delta: DeltaMessage {
+                        content: Some(" the weather.".to_string()),
+                        tool_calls: Some(vec![
+                            ToolCallChunk {
+                                index: 0,
+                                id: Some("call_0_0".to_string()),
+                                r#type: Some("function".to_string()),
+                                function: ToolCallFunctionObjChunk {
+                                    name: Some("get_weather".to_string()),
+                                    arguments: r#"{"location":"Boston"}"#.to_string(),
+                                },
+                            },
+                        ]),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }),
+                vec![tool_call.clone()],
+            ),
+            (
+                "finish",
+                r#"{"candidates": [{"content": {"parts": [{"text": ""}],"role": "model"},"finishReason": "STOP","index": 0}],"usageMetadata": {"promptTokenCount": 20,"candidatesTokenCount": 12,"thoughtsTokenCount": 8,"totalTokenCount": 40},"modelVersion": "gemini-2.0-flash","responseId": "resp_1"}"#,
+                chunk(Choice {
+                    index: 0,
+                    finish_reason: Some(OpenaiFinishReason::ToolCalls),
+                    ..Default::default()
+                }),
+                vec![],
+            ),
+        ];
+        for (name, data, want_chunk, want_tool_calls) in events {
+            let (got_chunk, got_tool_calls) = parser.parse_str(data).unwrap();
+            assert_eq!(got_chunk, want_chunk, "chunk not match: {}", name);
+            assert_eq!(
+                got_tool_calls, want_tool_calls,
+                "tool calls not match: {}",
+                name
+            );
+        }
+
+        let response = parser.response();
+        assert_eq!(response.id, "resp_1");
+        assert_eq!(response.object, "chat.completion");
+        assert_eq!(
+            response.choices[0].message.reasoning.as_deref(),
+            Some("Weather question.")
+        );
+        assert_eq!(
+            response.choices[0].message.content.as_deref(),
+            Some("Let me check the weather.")
+        );
+        assert_eq!(
+            response.choices[0].message.tool_calls,
+            Some(vec![tool_call])
+        );
+        assert_eq!(
+            response.choices[0].finish_reason,
+            Some(OpenaiFinishReason::ToolCalls)
+        );
+        assert_eq!(
+            response.usage,
+            OpenaiUsage {
+                completion_tokens: 20,
+                prompt_tokens: 20,
+                total_tokens: 40,
+                completion_tokens_details: Some(CompletionTokensDetails {
+                    reasoning_tokens: Some(8),
+                    ..Default::default()
+                }),
+                prompt_tokens_details: None,
+            }
+        );
+    }
+
+    #[test]
+    fn multiple_candidates() {
+        let events = vec![
+            r#"{"candidates":[{"content":{"parts":[{"text":"Hello"}],"role":"model"},"index":0},{"content":{"parts":[{"text":"Hi"}],"role":"model"},"index":1}],"responseId":"resp_2","modelVersion":"gemini-2.0-flash"}"#,
+            r#"{"candidates":[{"content":{"parts":[{"text":" there"}],"role":"model"},"finishReason":"STOP","index":0},{"content":{"parts":[{"text":"!"}],"role":"model"},"finishReason":"MAX_TOKENS","index":1}],"responseId":"resp_2","modelVersion":"gemini-2.0-flash"}"#,
+        ];
+        let mut parser = GeminiEventDataParser::default();
+        for event in events {
+            parser.parse_str(event).unwrap();
+        }
+        let got: Vec<_> = parser
+            .response()
+            .choices
+            .into_iter()
+            .map(|choice| (choice.index, choice.message.content, choice.finish_reason))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (
+                    0,
+                    Some("Hello there".to_string()),
+                    Some(OpenaiFinishReason::Stop)
+                ),
+                (1, Some("Hi!".to_string()), Some(OpenaiFinishReason::Length)),
+            ]
+        );
+    }
+
+    #[test]
+    fn tool_call_ids_per_candidate() {
+        let data = r#"{"candidates":[{"content":{"parts":[{"functionCall":{"name":"a","args":{}}}],"role":"model"},"index":0},{"content":{"parts":[{"functionCall":{"name":"b","args":{}}}],"role":"model"},"index":1}]}"#;
+        let mut parser = GeminiEventDataParser::default();
+        let (_, tool_calls) = parser.parse_str(data).unwrap();
+        let ids: Vec<_> = tool_calls.iter().map(|call| call.id()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "call_0_0", "call_1_0"
+            ]
+        );
+    }
+
+    #[test]
+    fn finish_reason() {
+        let tests = vec![
+            (
+                r#"{"candidates": [{"finishReason": "SAFETY","index": 0}]}"#,
+                OpenaiFinishReason::ContentFilter,
+            ),
+            (
+                r#"{"candidates": [{"content": {"parts": [{"text": "..."}],"role": "model"},"finishReason": "RECITATION","index": 0}]}"#,
+                OpenaiFinishReason::ContentFilter,
+            ),
+            (
+                r#"{"candidates": [{"finishReason": "MAX_TOKENS","index": 0}]}"#,
+                OpenaiFinishReason::Length,
+            ),
+            (
+                r#"{"promptFeedback": {"blockReason": "SAFETY"}}"#,
+                OpenaiFinishReason::ContentFilter,
+            ),
+            (
+                r#"{"candidates": [{"finishReason": "IMAGE_SAFETY","index": 0}]}"#,
+                OpenaiFinishReason::ContentFilter,
+            ),
+            (
+                r#"{"candidates": [{"content": {"parts": [{"executableCode": {"language": "PYTHON","code": "print(1)"}}],"role": "model"},"finishReason": "UNEXPECTED_TOOL_CALL","index": 0}]}"#,
+                OpenaiFinishReason::Stop,
+            ),
+        ];
+        for (data, want) in tests {
+            let mut parser = GeminiEventDataParser::default();
+            let (chunk, _) = parser.parse_str(data).unwrap();
+            let Some(Chunk::Data(chunk)) = chunk else {
+                panic!("no chunk for {}", data);
+            };
+            assert_eq!(
+                chunk.choices[0].finish_reason,
+                Some(want),
+                "finish reason not match: {}",
+                data
+            );
+            assert_eq!(parser.response().choices[0].finish_reason, Some(want));
+        }
+    }
+
+    #[test]
+    fn decode_sse() {
+        let body = "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hello\"}],\"role\": \"model\"},\"index\": 0}]}\r\n\r\ndata: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \" world\"}],\"role\": \"model\"},\"finishReason\": \"STOP\",\"index\": 0}]}\r\n\r\n";
+        let mut decoder = crate::sse::EventDecoder::<GenerateContentResponse>::new();
+        let texts: Vec<String> = decoder
+            .push(body.as_bytes())
+            .into_iter()
+            .map(|response| response.unwrap().text().unwrap())
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Hello", " world"
+            ]
+        );
+    }
+}
@@ -8,9 +8,19 @@ pub mod claude;
 
 pub mod magi;
 
+pub mod partial_json;
+
+pub mod sse;
+
+#[cfg(feature = "stream")]
+pub mod stream;
+
 #[cfg(feature = "gemini")]
 pub mod gemini;
 
+#[cfg(any(feature = "claude", feature = "gemini"))]
+pub mod convert;
+
 #[cfg(feature = "price")]
 mod price;
 
@@ -0,0 +1,343 @@
+use serde_json::{Map, Number, Value};
+
+/// A best-effort parse of a JSON document that may still be streaming, e.g. tool call arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialJson {
+    /// The document with unterminated strings, objects and arrays closed.
+    /// An object key whose value hasn't started yet is left out.
+    pub value: Value,
+    /// JSON pointers of the values not terminated yet, outermost first.
+    /// `""` is the root, it's only absent once the whole document is received.
+    pub incomplete: Vec<String>,
+}
+
+impl PartialJson {
+    pub fn is_complete(&self) -> bool {
+        self.incomplete.is_empty()
+    }
+}
+
+/// Parse the received prefix of a JSON document, returns `None` if it isn't valid JSON
+/// or nests deeper than 128 levels.
+///
+/// # Example
+///
+/// ````
+/// use await_openai::partial_json;
+/// use serde_json::json;
+///
+/// let partial = partial_json::parse(r#"{"city": "Par"#).unwrap();
+/// assert_eq!(partial.value, json!({"city": "Par"}));
+/// assert_eq!(partial.incomplete, vec!["", "/city"]);
+/// ````
+pub fn parse(input: &str) -> Option<PartialJson> {
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        incomplete: vec![],
+    };
+    let value = parser.value(String::new(), 0).ok()?;
+    // a complete document can only be followed by whitespace
+    parser.skip_whitespace();
+    if parser.pos < input.len() {
+        return None;
+    }
+    // pushed innermost first
+    parser.incomplete.reverse();
+    Some(PartialJson {
+        value: value.unwrap_or(Value::Null),
+        incomplete: parser.incomplete,
+    })
+}
+
+/// Nesting allowed before the input is rejected, the same limit as serde_json.
+const MAX_DEPTH: usize = 128;
+
+struct Invalid;
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    incomplete: Vec<String>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    /// `None` if the input ends before anything of the value could be read.
+    fn value(&mut self, path: String, depth: usize) -> Result<Option<Value>, Invalid> {
+        self.skip_whitespace();
+        let (value, complete) = match self.peek() {
+            None => (None, false),
+            Some(b'{') => {
+                let (value, complete) = self.object(&path, depth + 1)?;
+                (Some(value), complete)
+            }
+            Some(b'[') => {
+                let (value, complete) = self.array(&path, depth + 1)?;
+                (Some(value), complete)
+            }
+            Some(b'"') => {
+                let (value, complete) = self.string()?;
+                (Some(Value::String(value)), complete)
+            }
+            Some(b't') => self.literal("true", Value::Bool(true))?,
+            Some(b'f') => self.literal("false", Value::Bool(false))?,
+            Some(b'n') => self.literal("null", Value::Null)?,
+            Some(b'-' | b'0'..=b'9') => self.number()?,
+            Some(_) => return Err(Invalid),
+        };
+        if !complete {
+            self.incomplete.push(path);
+        }
+        Ok(value)
+    }
+
+    fn object(&mut self, path: &str, depth: usize) -> Result<(Value, bool), Invalid> {
+        if depth > MAX_DEPTH {
+            return Err(Invalid);
+        }
+        self.pos += 1;
+        let mut map = Map::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok((Value::Object(map), false)),
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok((Value::Object(map), true));
+                }
+                Some(b'"') => {}
+                Some(_) => return Err(Invalid),
+            }
+            let (key, complete) = self.string()?;
+            if !complete {
+                return Ok((Value::Object(map), false));
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok((Value::Object(map), false)),
+                Some(b':') => self.pos += 1,
+                Some(_) => return Err(Invalid),
+            }
+            let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+            if let Some(value) = self.value(child, depth)? {
+                map.insert(key, value);
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok((Value::Object(map), false)),
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok((Value::Object(map), true));
+                }
+                Some(_) => return Err(Invalid),
+            }
+        }
+    }
+
+    fn array(&mut self, path: &str, depth: usize) -> Result<(Value, bool), Invalid> {
+        if depth > MAX_DEPTH {
+            return Err(Invalid);
+        }
+        self.pos += 1;
+        let mut values = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok((Value::Array(values), false)),
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok((Value::Array(values), true));
+                }
+                Some(_) => {}
+            }
+            if let Some(value) = self.value(format!("{}/{}", path, values.len()), depth)? {
+                values.push(value);
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok((Value::Array(values), false)),
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok((Value::Array(values), true));
+                }
+                Some(_) => return Err(Invalid),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<(String, bool), Invalid> {
+        let start = self.pos;
+        let bytes = self.input.as_bytes();
+        let mut i = start + 1;
+        // bytes of a multi-byte character never match the quote or the backslash
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => {
+                    self.pos = i + 1;
+                    let value =
+                        serde_json::from_str(&self.input[start..self.pos]).map_err(|_| Invalid)?;
+                    return Ok((value, true));
+                }
+                _ => i += 1,
+            }
+        }
+        self.pos = self.input.len();
+        // drop a trailing escape sequence that isn't complete yet, e.g. `\u00`
+        let raw = &self.input[start + 1..];
+        for cut in 0..=raw.len().min(12) {
+            let end = raw.len() - cut;
+            if !raw.is_char_boundary(end) {
+                continue;
+            }
+            if let Ok(value) = serde_json::from_str(&format!("\"{}\"", &raw[..end])) {
+                return Ok((value, false));
+            }
+        }
+        Err(Invalid)
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<(Option<Value>, bool), Invalid> {
+        let rest = &self.input[self.pos..];
+        if rest.starts_with(word) {
+            self.pos += word.len();
+            Ok((Some(value), true))
+        } else if word.starts_with(rest) {
+            self.pos = self.input.len();
+            Ok((Some(value), false))
+        } else {
+            Err(Invalid)
+        }
+    }
+
+    fn number(&mut self) -> Result<(Option<Value>, bool), Invalid> {
+        let start = self.pos;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.pos += 1;
+        }
+        let text = &self.input[start..self.pos];
+        if self.pos < self.input.len() {
+            let number = text.parse::<Number>().map_err(|_| Invalid)?;
+            return Ok((Some(Value::Number(number)), true));
+        }
+        // more digits may follow, keep the longest prefix that is a number
+        let text = text.trim_end_matches([
+            '-', '+', '.', 'e', 'E',
+        ]);
+        Ok((text.parse::<Number>().ok().map(Value::Number), false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_partial() {
+        let deep = "[".repeat(100_000);
+        let tests = vec![
+            ("empty", "", Some((json!(null), vec![""]))),
+            (
+                "complete",
+                r#"{"a": [1, "b"]}"#,
+                Some((json!({"a": [1, "b"]}), vec![])),
+            ),
+            (
+                "open object",
+                r#"{"a": 1"#,
+                Some((json!({"a": 1}), vec!["", "/a"])),
+            ),
+            (
+                "partial key",
+                r#"{"a": 1, "ci"#,
+                Some((json!({"a": 1}), vec![""])),
+            ),
+            (
+                "missing value",
+                r#"{"city": "#,
+                Some((
+                    json!({}),
+                    vec![
+                        "", "/city",
+                    ],
+                )),
+            ),
+            (
+                "partial string",
+                r#"{"city": "Par"#,
+                Some((
+                    json!({"city": "Par"}),
+                    vec![
+                        "", "/city",
+                    ],
+                )),
+            ),
+            (
+                "partial escape",
+                r#"{"text": "caf\u00"#,
+                Some((
+                    json!({"text": "caf"}),
+                    vec![
+                        "", "/text",
+                    ],
+                )),
+            ),
+            (
+                "nested array",
+                r#"{"a": {"b~/c": [true, fa"#,
+                Some((
+                    json!({"a": {"b~/c": [true, false]}}),
+                    vec![
+                        "",
+                        "/a",
+                        "/a/b~0~1c",
+                        "/a/b~0~1c/1",
+                    ],
+                )),
+            ),
+            (
+                "partial number",
+                "[1, -",
+                Some((json!([1]), vec!["", "/1"])),
+            ),
+            ("number", "[12.5e", Some((json!([12.5]), vec!["", "/0"]))),
+            (
+                "multi-byte",
+                r#"["北京"#,
+                Some((json!(["北京"]), vec!["", "/0"])),
+            ),
+            ("invalid", r#"{"a": x"#, None),
+            (
+                "trailing whitespace",
+                "{\"a\": 1} \n",
+                Some((json!({"a": 1}), vec![])),
+            ),
+            ("trailing input", r#"{"a":1}xyz"#, None),
+            ("too deep", &deep, None),
+        ];
+        for (name, input, want) in tests {
+            let got = parse(input).map(|partial| (partial.value, partial.incomplete));
+            let want = want.map(|(value, incomplete)| {
+                (value, incomplete.into_iter().map(String::from).collect())
+            });
+            assert_eq!(got, want, "partial json test failed: {}", name);
+        }
+    }
+}
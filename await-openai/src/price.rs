@@ -1,15 +1,22 @@
 use crate::entity::chat_completion_object::Usage;
 
 pub fn price(model: &str, usage: &Usage) -> f32 {
-    let (prompt_price, completion_price) = match model {
-        "gpt-4o" => (0.005, 0.015),
-        "gpt-4-turbo" => (0.01, 0.03),
-        "gpt-4" => (0.03, 0.06),
-        "gpt-3.5-turbo" => (0.0005, 0.0015),
-        "gpt-3.5-turbo-instruct" => (0.0015, 0.002),
+    let (prompt_price, cached_price, completion_price) = match model {
+        "gpt-4o" => (0.005, 0.0025, 0.015),
+        "gpt-4-turbo" => (0.01, 0.01, 0.03),
+        "gpt-4" => (0.03, 0.03, 0.06),
+        "gpt-3.5-turbo" => (0.0005, 0.0005, 0.0015),
+        "gpt-3.5-turbo-instruct" => (0.0015, 0.0015, 0.002),
         _ => return 0.0, // Early return on unknown model
     };
-    let total_price = (usage.prompt_tokens as f32 * prompt_price)
+    let cached_tokens = usage
+        .prompt_tokens_details
+        .as_ref()
+        .and_then(|details| details.cached_tokens)
+        .unwrap_or_default()
+        .min(usage.prompt_tokens);
+    let total_price = ((usage.prompt_tokens - cached_tokens) as f32 * prompt_price)
+        + (cached_tokens as f32 * cached_price)
         + (usage.completion_tokens as f32 * completion_price);
     total_price / 1000.0
 }
@@ -0,0 +1,422 @@
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use crate::entity::{chat_completion_chunk::Chunk, chat_completion_object::ErrResponse};
+
+/// A dispatched server-sent event.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The `event` field, `None` for the default `message` event.
+    pub event: Option<String>,
+    /// The `data` lines joined with `\n`.
+    pub data: String,
+    pub id: Option<String>,
+    /// The reconnection time in milliseconds.
+    pub retry: Option<u64>,
+}
+
+/// Writes the event in the `text/event-stream` format, terminated by the blank line.
+impl fmt::Display for SseEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(event) = self.event.as_ref() {
+            writeln!(f, "event: {}", event)?;
+        }
+        if let Some(id) = self.id.as_ref() {
+            writeln!(f, "id: {}", id)?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {}", retry)?;
+        }
+        // a line break in the data would end the field
+        for line in self.data.split('\n') {
+            writeln!(f, "data: {}", line.strip_suffix('\r').unwrap_or(line))?;
+        }
+        writeln!(f)
+    }
+}
+
+/// SseDecoder turns the bytes of an `text/event-stream` body into events.
+/// It accepts arbitrary slices, lines and UTF-8 characters may be split across calls to `push`.
+///
+/// # Example
+///
+/// ````
+/// use await_openai::sse::SseDecoder;
+///
+/// let mut decoder = SseDecoder::default();
+/// assert!(decoder.push(b"event: ping\r\nda").is_empty());
+/// let events = decoder.push(b"ta: {}\r\n\r\n");
+/// assert_eq!(events[0].event.as_deref(), Some("ping"));
+/// assert_eq!(events[0].data, "{}");
+/// ````
+#[derive(Debug, Default, Clone)]
+pub struct SseDecoder {
+    line: Vec<u8>,
+    /// The last line ended with `\r`, a following `\n` belongs to it.
+    skip_lf: bool,
+    started: bool,
+    event: Option<String>,
+    data: String,
+    has_data: bool,
+    id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next bytes of the body, returns the events completed by them.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        let mut events = vec![];
+        for &byte in bytes {
+            if self.skip_lf {
+                self.skip_lf = false;
+                if byte == b'\n' {
+                    continue;
+                }
+            }
+            match byte {
+                b'\r' => {
+                    self.skip_lf = true;
+                    events.extend(self.process_line());
+                }
+                b'\n' => events.extend(self.process_line()),
+                _ => self.line.push(byte),
+            }
+        }
+        events
+    }
+
+    /// Call at the end of the body. Unlike the spec, an event without the trailing blank line is still dispatched.
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let mut event = None;
+        if !self.line.is_empty() {
+            event = self.process_line();
+        }
+        event.or_else(|| self.dispatch())
+    }
+
+    fn process_line(&mut self) -> Option<SseEvent> {
+        let bytes = std::mem::take(&mut self.line);
+        let mut line = String::from_utf8_lossy(&bytes);
+        if !self.started {
+            self.started = true;
+            if let Some(stripped) = line.strip_prefix('\u{feff}') {
+                line = stripped.to_string().into();
+            }
+        }
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        // comments are used as keep-alives
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(retry) = value.parse() {
+                    self.retry = Some(retry);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        let retry = self.retry.take();
+        if !self.has_data {
+            return None;
+        }
+        self.has_data = false;
+        Some(SseEvent {
+            event,
+            data: std::mem::take(&mut self.data),
+            // the last event id persists across events
+            id: self.id.clone(),
+            retry,
+        })
+    }
+}
+
+/// Types which can be decoded from a server-sent event.
+pub trait FromSseEvent: Sized {
+    type Error;
+
+    fn from_sse_event(event: &SseEvent) -> Result<Self, Self::Error>;
+}
+
+impl FromSseEvent for Chunk {
+    type Error = <Chunk as FromStr>::Err;
+
+    fn from_sse_event(event: &SseEvent) -> Result<Self, Self::Error> {
+        Chunk::from_str(&event.data)
+    }
+}
+
+/// Types which can be encoded as a server-sent event, use `to_string` on the event for the framed text.
+///
+/// # Example
+///
+/// ````
+/// use await_openai::{entity::chat_completion_chunk::Chunk, sse::ToSseEvent};
+///
+/// let event = Chunk::Done.to_sse_event().unwrap();
+/// assert_eq!(event.to_string(), "data: [DONE]\n\n");
+/// ````
+pub trait ToSseEvent {
+    fn to_sse_event(&self) -> Result<SseEvent, serde_json::Error>;
+}
+
+impl ToSseEvent for Chunk {
+    fn to_sse_event(&self) -> Result<SseEvent, serde_json::Error> {
+        Ok(SseEvent {
+            data: self.try_to_string()?,
+            ..Default::default()
+        })
+    }
+}
+
+/// An error in the middle of an Openai compatible stream, sent as `data: {"error": {...}}`.
+impl ToSseEvent for ErrResponse {
+    fn to_sse_event(&self) -> Result<SseEvent, serde_json::Error> {
+        Ok(SseEvent {
+            data: serde_json::to_string(self)?,
+            ..Default::default()
+        })
+    }
+}
+
+/// EventDecoder decodes the bytes of a stream into typed items, e.g. `Chunk` for Openai.
+///
+/// # Example
+///
+/// ````
+/// use await_openai::{entity::chat_completion_chunk::Chunk, sse::EventDecoder};
+///
+/// let mut decoder = EventDecoder::<Chunk>::default();
+/// let chunks = decoder.push(b": keep-alive\n\ndata: [DONE]\n\n");
+/// assert_eq!(chunks.len(), 1);
+/// assert_eq!(chunks[0].as_ref().unwrap(), &Chunk::Done);
+/// ````
+#[derive(Debug, Clone)]
+pub struct EventDecoder<T> {
+    decoder: SseDecoder,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventDecoder<T> {
+    fn default() -> Self {
+        Self {
+            decoder: SseDecoder::default(),
+            _item: PhantomData,
+        }
+    }
+}
+
+impl<T: FromSseEvent> EventDecoder<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next bytes of the body, returns the items completed by them.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Result<T, T::Error>> {
+        self.decoder
+            .push(bytes)
+            .iter()
+            .map(T::from_sse_event)
+            .collect()
+    }
+
+    /// Call at the end of the body.
+    pub fn finish(&mut self) -> Option<Result<T, T::Error>> {
+        self.decoder.finish().map(|event| T::from_sse_event(&event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(data: &str) -> SseEvent {
+        SseEvent {
+            data: data.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn decode() {
+        let tests = vec![
+            (
+                "lf",
+                vec!["data: a\n\ndata: b\n\n"],
+                vec![
+                    data("a"),
+                    data("b"),
+                ],
+            ),
+            ("crlf", vec!["data: a\r\n\r\n"], vec![data("a")]),
+            (
+                "cr",
+                vec!["data: a\r\rdata: b\r\r"],
+                vec![
+                    data("a"),
+                    data("b"),
+                ],
+            ),
+            (
+                "crlf split after cr",
+                vec![
+                    "data: a\r",
+                    "\n\r",
+                    "\n",
+                ],
+                vec![data("a")],
+            ),
+            (
+                "partial lines",
+                vec![
+                    "da",
+                    "ta: {\"a\"",
+                    ":1}\n",
+                    "\n",
+                ],
+                vec![data(
+                    "{\"a\":1}",
+                )],
+            ),
+            (
+                "multi-line data",
+                vec!["data: a\ndata:b\ndata\n\n"],
+                vec![data(
+                    "a\nb\n",
+                )],
+            ),
+            (
+                "comments and keep-alives",
+                vec![": ping\n\n:\n\ndata: a\n: in between\n\n"],
+                vec![data("a")],
+            ),
+            (
+                "event without data",
+                vec!["event: ping\n\ndata: a\n\n"],
+                vec![data("a")],
+            ),
+            (
+                "event name, id and retry",
+                vec!["event: message_start\nid: 1\nretry: 3000\ndata: {}\n\ndata: b\n\n"],
+                vec![
+                    SseEvent {
+                        event: Some("message_start".to_string()),
+                        data: "{}".to_string(),
+                        id: Some("1".to_string()),
+                        retry: Some(3000),
+                    },
+                    SseEvent {
+                        data: "b".to_string(),
+                        id: Some("1".to_string()),
+                        ..Default::default()
+                    },
+                ],
+            ),
+            ("bom", vec!["\u{feff}data: a\n\n"], vec![data("a")]),
+            ("no trailing blank line", vec!["data: a"], vec![data("a")]),
+        ];
+        for (name, pushes, want) in tests {
+            let mut decoder = SseDecoder::new();
+            let mut got = vec![];
+            for bytes in pushes {
+                got.extend(decoder.push(bytes.as_bytes()));
+            }
+            got.extend(decoder.finish());
+            assert_eq!(got, want, "decode test failed: {}", name);
+        }
+    }
+
+    #[test]
+    fn decode_split_utf8() {
+        let bytes = "data: 你好\n\n".as_bytes();
+        let mut decoder = SseDecoder::new();
+        let mut got = vec![];
+        for byte in bytes.chunks(1) {
+            got.extend(decoder.push(byte));
+        }
+        assert_eq!(
+            got,
+            vec![data(
+                "你好"
+            )]
+        );
+    }
+
+    #[test]
+    fn decode_chunk() {
+        let body = "data: {\"id\":\"chatcmpl-123\",\"object\":\"chat.completion.chunk\",\"created\":1694268190,\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hi\"},\"finish_reason\":null}]}\n\ndata: [DONE]\n\n";
+        let mut decoder = EventDecoder::<Chunk>::new();
+        let chunks: Vec<Chunk> = decoder
+            .push(body.as_bytes())
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(chunks.len(), 2);
+        let Chunk::Data(response) = &chunks[0] else {
+            panic!("expected a data chunk");
+        };
+        assert_eq!(response.choices[0].delta.content.as_deref(), Some("Hi"));
+        assert_eq!(chunks[1], Chunk::Done);
+        assert!(decoder.finish().is_none());
+    }
+
+    #[test]
+    fn encode() {
+        let tests = vec![
+            ("done", data("[DONE]"), "data: [DONE]\n\n"),
+            (
+                "event name and id",
+                SseEvent {
+                    event: Some("ping".to_string()),
+                    data: r#"{"type": "ping"}"#.to_string(),
+                    id: Some("1".to_string()),
+                    retry: None,
+                },
+                "event: ping\nid: 1\ndata: {\"type\": \"ping\"}\n\n",
+            ),
+            ("multi-line data", data("a\r\nb"), "data: a\ndata: b\n\n"),
+        ];
+        for (name, event, want) in tests {
+            let got = event.to_string();
+            assert_eq!(got, want, "encode test failed: {}", name);
+            let mut decoder = SseDecoder::new();
+            let decoded = decoder.push(got.as_bytes());
+            assert_eq!(decoded.len(), 1, "round trip failed: {}", name);
+        }
+
+        let err = ErrResponse {
+            error: crate::entity::chat_completion_object::Err {
+                message: "Overloaded".to_string(),
+                r#type: "server_error".to_string(),
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            err.to_sse_event().unwrap().to_string(),
+            "data: {\"error\":{\"message\":\"Overloaded\",\"type\":\"server_error\",\"param\":\"\",\"code\":\"\"}}\n\n"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+use crate::{
+    magi::EventDataParser,
+    sse::{EventDecoder, FromSseEvent},
+};
+
+/// An item decoded from the stream and what the parser returned for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parsed<T, O> {
+    pub event: T,
+    /// The parser's output, e.g. the completed tool calls.
+    pub output: O,
+}
+
+#[derive(Debug)]
+pub enum StreamError<E, D, P> {
+    /// Returned by the underlying stream.
+    Transport(E),
+    /// The event couldn't be decoded into the parser's input.
+    Decode(D),
+    Parse(P),
+}
+
+impl<E: fmt::Display, D: fmt::Display, P: fmt::Display> fmt::Display for StreamError<E, D, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Transport(e) => write!(f, "transport error: {}", e),
+            StreamError::Decode(e) => write!(f, "decode error: {}", e),
+            StreamError::Parse(e) => write!(f, "parse error: {}", e),
+        }
+    }
+}
+
+impl<E, D, P> std::error::Error for StreamError<E, D, P>
+where
+    E: fmt::Debug + fmt::Display,
+    D: fmt::Debug + fmt::Display,
+    P: fmt::Debug + fmt::Display,
+{
+}
+
+type ParsedResult<T, E, P> = Result<
+    Parsed<T, <P as EventDataParser<T>>::Output>,
+    StreamError<E, <T as FromSseEvent>::Error, <P as EventDataParser<T>>::Error>,
+>;
+
+/// Adapters over a stream of response bytes, e.g. `reqwest::Response::bytes_stream`.
+///
+/// # Example
+///
+/// ````
+/// use await_openai::{entity::chat_completion_chunk::OpenaiEventDataParser, stream::EventStreamExt};
+/// use futures::{executor::block_on, stream};
+///
+/// let body = stream::iter(vec![
+///     Ok::<_, std::io::Error>(r#"data: {"id":"1","object":"chat.completion.chunk","created":1,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":"Hi"},"finish_reason":"stop"}]}"#),
+///     Ok("\n\ndata: [DONE]\n\n"),
+/// ]);
+/// let response = block_on(body.parse_events(OpenaiEventDataParser::default()).response()).unwrap();
+/// assert_eq!(response.choices[0].message.content.as_deref(), Some("Hi"));
+/// ````
+pub trait EventStreamExt<B: AsRef<[u8]>, E>: Stream<Item = Result<B, E>> + Sized {
+    /// Decode the server-sent events and feed each of them to `parser`.
+    fn parse_events<T, P>(self, parser: P) -> ParsedStream<Self, T, P>
+    where
+        T: FromSseEvent,
+        P: EventDataParser<T>,
+    {
+        ParsedStream::new(self, parser)
+    }
+}
+
+impl<S, B, E> EventStreamExt<B, E> for S
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+{
+}
+
+/// Yields every decoded event with the parser's output, see [`EventStreamExt::parse_events`].
+pub struct ParsedStream<S, T: FromSseEvent, P> {
+    stream: Pin<Box<S>>,
+    decoder: EventDecoder<T>,
+    pending: VecDeque<Result<T, T::Error>>,
+    parser: P,
+    done: bool,
+}
+
+// no field is pinned, the inner stream is boxed
+impl<S, T: FromSseEvent, P> Unpin for ParsedStream<S, T, P> {}
+
+impl<S, B, E, T, P> ParsedStream<S, T, P>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    T: FromSseEvent,
+    P: EventDataParser<T>,
+{
+    pub fn new(stream: S, parser: P) -> Self {
+        Self {
+            stream: Box::pin(stream),
+            decoder: EventDecoder::new(),
+            pending: VecDeque::new(),
+            parser,
+            done: false,
+        }
+    }
+
+    pub fn parser(&self) -> &P {
+        &self.parser
+    }
+
+    /// Drive the rest of the stream and resolve to the parser's unary response.
+    /// Resolves to the first error instead.
+    pub fn response(self) -> ResponseFuture<S, T, P> {
+        ResponseFuture { stream: Some(self) }
+    }
+
+    fn parse(&mut self, event: Result<T, T::Error>) -> ParsedResult<T, E, P> {
+        let event = event.map_err(StreamError::Decode)?;
+        let output = self.parser.parse(&event).map_err(StreamError::Parse)?;
+        Ok(Parsed { event, output })
+    }
+}
+
+impl<S, B, E, T, P> Stream for ParsedStream<S, T, P>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    T: FromSseEvent,
+    P: EventDataParser<T>,
+{
+    type Item = ParsedResult<T, E, P>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(event) = this.pending.pop_front() {
+                return Poll::Ready(Some(this.parse(event)));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(bytes))) => {
+                    this.pending.extend(this.decoder.push(bytes.as_ref()));
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    return Poll::Ready(Some(Err(StreamError::Transport(e))));
+                }
+                Poll::Ready(None) => {
+                    this.done = true;
+                    this.pending.extend(this.decoder.finish());
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Returned by [`ParsedStream::response`].
+pub struct ResponseFuture<S, T: FromSseEvent, P> {
+    stream: Option<ParsedStream<S, T, P>>,
+}
+
+impl<S, B, E, T, P> Future for ResponseFuture<S, T, P>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    T: FromSseEvent,
+    P: EventDataParser<T>,
+{
+    type Output = Result<P::UnarayResponse, StreamError<E, T::Error, P::Error>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let Some(stream) = this.stream.as_mut() else {
+            panic!("ResponseFuture polled after completion");
+        };
+        loop {
+            match Pin::new(&mut *stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(_))) => {}
+                Poll::Ready(Some(Err(e))) => {
+                    this.stream = None;
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Ready(None) => {
+                    let stream = this.stream.take().unwrap();
+                    return Poll::Ready(Ok(stream.parser.response()));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{StreamExt, executor::block_on, stream};
+
+    use super::*;
+    use crate::entity::{
+        chat_completion_chunk::{Chunk, OpenaiEventDataParser},
+        create_chat_completion::ToolCall,
+    };
+
+    const BODY: &str = concat!(
+        r#"data: {"id":"1","object":"chat.completion.chunk","created":1,"model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"get_weather","arguments":""}}]},"finish_reason":null}]}"#,
+        "\n\n",
+        r#"data: {"id":"1","object":"chat.completion.chunk","created":1,"model":"gpt-4o","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"city\":\"Paris\"}"}}]},"finish_reason":null}]}"#,
+        "\n\n",
+        r#"data: {"id":"1","object":"chat.completion.chunk","created":1,"model":"gpt-4o","choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#,
+        "\n\n",
+        "data: [DONE]\n\n",
+    );
+
+    fn body(size: usize) -> impl Stream<Item = Result<Vec<u8>, std::io::Error>> {
+        stream::iter(
+            BODY.as_bytes()
+                .chunks(size)
+                .map(|b| Ok(b.to_vec()))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn parse_events() {
+        for size in [
+            1,
+            7,
+            BODY.len(),
+        ] {
+            let items: Vec<_> = block_on(
+                body(size)
+                    .parse_events(OpenaiEventDataParser::default())
+                    .map(|item| item.unwrap())
+                    .collect(),
+            );
+            assert_eq!(items.len(), 4, "chunk size {}", size);
+            assert_eq!(items[3].event, Chunk::Done);
+            let tool_calls: Vec<_> = items.iter().flat_map(|item| &item.output).collect();
+            assert_eq!(tool_calls.len(), 1, "chunk size {}", size);
+            let ToolCall::Function(call) = tool_calls[0];
+            assert_eq!(call.function.arguments, r#"{"city":"Paris"}"#);
+
+            let response = block_on(
+                body(size)
+                    .parse_events(OpenaiEventDataParser::default())
+                    .response(),
+            )
+            .unwrap();
+            assert_eq!(
+                response.choices[0].message.tool_calls.as_ref().unwrap()[0].id(),
+                "call_1"
+            );
+        }
+    }
+
+    #[test]
+    fn stream_error() {
+        let tests = vec![
+            (
+                "transport",
+                vec![
+                    Ok::<_, &str>("data: [DONE]\n\n"),
+                    Err("reset"),
+                ],
+                "transport error: reset",
+            ),
+            (
+                "decode",
+                vec![Ok(
+                    "data: {\"id\":\n\n",
+                )],
+                "decode error: EOF while parsing a value at line 1 column 6",
+            ),
+        ];
+        for (name, chunks, want) in tests {
+            let got = block_on(
+                stream::iter(chunks)
+                    .parse_events(OpenaiEventDataParser::default())
+                    .response(),
+            );
+            assert_eq!(got.unwrap_err().to_string(), want, "{}", name);
+        }
+    }
+}
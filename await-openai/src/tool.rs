@@ -9,6 +9,7 @@ use schemars::{
 use serde_json::Value;
 
 use crate::entity::create_chat_completion::{FunctionTool, Tool, ToolType};
+#[cfg(feature = "claude")]
 use async_claude::messages::Tool as ClaudeTool;
 pub use paste;
 
@@ -126,16 +127,10 @@ fn parse_function_param<T: JsonSchema>() -> Result<serde_json::Value> {
     Ok(json_value)
 }
 
+#[cfg(feature = "claude")]
 impl From<ClaudeTool> for Tool {
     fn from(claude_tool: ClaudeTool) -> Self {
-        Tool {
-            r#type: ToolType::Function,
-            function: FunctionTool {
-                name: claude_tool.name.clone(),
-                description: claude_tool.description,
-                parameters: Some(claude_tool.input_schema),
-            },
-        }
+        crate::claude::openai_tool(claude_tool)
     }
 }
 